@@ -0,0 +1,214 @@
+//! Short animations played over the level as it's drawn: cells flipping over when revealed,
+//! shaking on a mistake, fading in and out of hover and a ripple when the level is completed.
+//! They are kept apart from `Level`, which only holds the state of the puzzle.
+
+use std::{
+    collections::HashMap,
+    f32::consts::PI,
+    time::{Duration, Instant},
+};
+
+use cgmath::Vector2;
+
+use crate::hexagon::{hex_distance, Axial};
+
+/// Lengths of the animations, in seconds.
+const FLIP_DURATION: f32 = 0.3;
+const SHAKE_DURATION: f32 = 0.4;
+const HOVER_FADE_DURATION: f32 = 0.12;
+const RIPPLE_DURATION: f32 = 0.5;
+/// How much later the ripple reaches each step further from where it started.
+const RIPPLE_STEP_DELAY: f32 = 0.06;
+
+const SHAKE_FREQUENCY: f32 = 6.0;
+/// How far a shaking cell moves, relative to the cell size.
+const SHAKE_DISTANCE: f32 = 0.15;
+/// How much the ripple grows cells, relative to their size.
+const RIPPLE_GROWTH: f32 = 0.15;
+
+/// Frames further apart than this are treated as a pause, so animations don't skip ahead.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// How a cell looks at the current moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellAnimation {
+    /// How far the cell is moved from its place, relative to the cell size.
+    pub offset: Vector2<f32>,
+    /// How much the cell is stretched horizontally and vertically.
+    pub stretch: Vector2<f32>,
+    /// How hovered the cell looks, from 0 to 1.
+    pub hover: f32,
+    /// Whether a cell being revealed has turned far enough to show its revealed side.
+    pub shows_revealed: bool,
+    /// Whether the cell is still flipping over, so its count isn't shown yet.
+    pub flipping: bool,
+}
+
+impl Default for CellAnimation {
+    /// A cell at rest.
+    fn default() -> CellAnimation {
+        CellAnimation {
+            offset: Vector2::new(0.0, 0.0),
+            stretch: Vector2::new(1.0, 1.0),
+            hover: 0.0,
+            shows_revealed: true,
+            flipping: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Animations {
+    /// Turns the movement off, so changes show right away.
+    pub reduce_motion: bool,
+
+    /// Seconds the animations have run for.
+    time: f32,
+    last_tick: Option<Instant>,
+
+    /// When each flip and shake started.
+    flips: HashMap<Axial, f32>,
+    shakes: HashMap<Axial, f32>,
+    hover: HashMap<Axial, f32>,
+    ripple: Option<Ripple>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ripple {
+    origin: Axial,
+    start: f32,
+    end: f32,
+}
+
+impl Animations {
+    pub fn new() -> Animations {
+        Animations::default()
+    }
+
+    /// Advances the animations to `now`, fading hover towards the `hovered` cell.
+    pub fn tick(&mut self, now: Instant, hovered: Axial) {
+        let elapsed = match self.last_tick {
+            Some(last) => now.saturating_duration_since(last).min(MAX_FRAME_TIME),
+            None => Duration::from_secs(0),
+        };
+
+        self.advance(elapsed.as_secs_f32(), hovered);
+
+        // Nothing moves while idle, so the wait for the next change isn't counted.
+        self.last_tick = if self.is_active() { Some(now) } else { None };
+    }
+
+    pub fn advance(&mut self, seconds: f32, hovered: Axial) {
+        self.time += seconds;
+        let time = self.time;
+
+        self.flips.retain(|_, start| time - *start < FLIP_DURATION);
+        self.shakes
+            .retain(|_, start| time - *start < SHAKE_DURATION);
+
+        if matches!(self.ripple, Some(ripple) if time >= ripple.end) {
+            self.ripple = None;
+        }
+
+        let fade = if self.reduce_motion {
+            1.0
+        } else {
+            seconds / HOVER_FADE_DURATION
+        };
+
+        for (position, amount) in self.hover.iter_mut() {
+            if *position != hovered {
+                *amount -= fade;
+            }
+        }
+
+        let amount = self.hover.entry(hovered).or_insert(0.0);
+        *amount = (*amount + fade).min(1.0);
+
+        // The hovered cell is kept even before it starts fading in.
+        self.hover
+            .retain(|position, amount| *amount > 0.0 || *position == hovered);
+    }
+
+    /// Whether anything is still moving.
+    pub fn is_active(&self) -> bool {
+        !self.flips.is_empty()
+            || !self.shakes.is_empty()
+            || self.ripple.is_some()
+            || self.hover.values().any(|amount| *amount < 1.0)
+    }
+
+    /// Stops everything, for when the level is replaced or play starts over.
+    pub fn clear(&mut self) {
+        self.flips.clear();
+        self.shakes.clear();
+        self.ripple = None;
+    }
+
+    pub fn reveal(&mut self, position: Axial) {
+        if !self.reduce_motion {
+            self.flips.insert(position, self.time);
+        }
+    }
+
+    pub fn mistake(&mut self, position: Axial) {
+        if !self.reduce_motion {
+            self.shakes.insert(position, self.time);
+        }
+    }
+
+    /// Starts a ripple from `origin` across `positions`, once the last cell has flipped over.
+    pub fn complete(&mut self, origin: Axial, positions: impl Iterator<Item = Axial>) {
+        if self.reduce_motion {
+            return;
+        }
+
+        let furthest = positions
+            .map(|position| hex_distance(origin, position))
+            .max()
+            .unwrap_or(0);
+
+        let start = self.time + FLIP_DURATION;
+
+        self.ripple = Some(Ripple {
+            origin,
+            start,
+            end: start + furthest as f32 * RIPPLE_STEP_DELAY + RIPPLE_DURATION,
+        });
+    }
+
+    pub fn cell(&self, position: Axial) -> CellAnimation {
+        let mut animation = CellAnimation {
+            hover: self.hover.get(&position).copied().unwrap_or(0.0),
+            ..CellAnimation::default()
+        };
+
+        // Flips turn the cell edge on halfway through, showing the revealed side after that.
+        if let Some(start) = self.flips.get(&position) {
+            let progress = ((self.time - start) / FLIP_DURATION).clamp(0.0, 1.0);
+            animation.stretch.x = (progress * PI).cos().abs();
+            animation.shows_revealed = progress >= 0.5;
+            animation.flipping = true;
+        }
+
+        if let Some(start) = self.shakes.get(&position) {
+            let elapsed = self.time - start;
+            let fade = 1.0 - elapsed / SHAKE_DURATION;
+            let swing = (elapsed * SHAKE_FREQUENCY * 2.0 * PI).sin();
+            animation.offset.x = swing * SHAKE_DISTANCE * fade.max(0.0);
+        }
+
+        if let Some(ripple) = self.ripple {
+            let delay = hex_distance(ripple.origin, position) as f32 * RIPPLE_STEP_DELAY;
+            let progress = (self.time - ripple.start - delay) / RIPPLE_DURATION;
+
+            if progress > 0.0 && progress < 1.0 {
+                let wave = (progress * PI).sin();
+                animation.stretch *= 1.0 + wave * RIPPLE_GROWTH;
+                animation.hover = animation.hover.max(wave);
+            }
+        }
+
+        animation
+    }
+}
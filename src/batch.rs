@@ -0,0 +1,34 @@
+use rayon::prelude::*;
+
+use crate::{
+    difficulty::{rate_trace, Difficulty},
+    level::Level,
+    rules::Puzzle,
+    solver::solve_puzzle,
+    uniqueness::{check_puzzle_uniqueness, Uniqueness},
+};
+
+#[derive(Debug)]
+pub struct LevelAnalysis {
+    pub uniqueness: Uniqueness,
+    pub difficulty: Difficulty,
+}
+
+pub fn analyze_puzzle(puzzle: &Puzzle) -> LevelAnalysis {
+    let (uniqueness, trace) =
+        rayon::join(|| check_puzzle_uniqueness(puzzle), || solve_puzzle(puzzle));
+
+    LevelAnalysis {
+        uniqueness,
+        difficulty: rate_trace(&trace),
+    }
+}
+
+/// Analyzes a pack of levels in parallel, returning the results in the same order.
+pub fn analyze_levels(levels: &[Level]) -> Vec<LevelAnalysis> {
+    // Levels keep play state in `Cell`s and can't be shared between threads, so they are
+    // flattened into puzzles first.
+    let puzzles: Vec<Puzzle> = levels.iter().map(Puzzle::from_level).collect();
+
+    puzzles.par_iter().map(analyze_puzzle).collect()
+}
@@ -0,0 +1,583 @@
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::Path,
+    process,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use opencells_rs::{
+    batch::analyze_levels,
+    formats::{read_level, write_level, Format},
+    generator::{generate, GeneratorParams},
+    hexagon::Axial,
+    level::Level,
+    prune::prune,
+    raster::rasterize,
+    rules::HintSource,
+    scene::level_scene,
+    shapes::{Mask, Shape},
+    solver::solve,
+    svg::{render_svg, Orientation, SvgOptions, SvgStyle},
+    theme::Theme,
+    uniqueness::Uniqueness,
+    validate::{validate, Severity},
+};
+
+const USAGE: &str = "\
+Usage: opencells <command> [options]
+
+Commands:
+  solve <level>              Print the deduction steps that solve a level
+  validate <level>...        List problems with the layout and hints of levels
+  check <level>...           Check that levels are valid and have a unique solution that can be
+                             deduced
+  convert <input> <output>   Convert a level between formats
+  generate                   Generate a level
+  render <level>             Draw a level as SVG or PNG
+
+Levels are read and written in the format matching their extension (.opencells, .hexcells or
+.sixcells), or the one given with --format, --from or --to. `-` reads from standard input or
+writes to standard output.
+
+Options for generate:
+  --seed <number>            Seed to generate from, picked from the clock by default
+  --shape <shape>            hexagon:<radius>, triangle:<size>, rhombus:<w>x<h> or
+                             rectangle:<w>x<h>
+  --mask <file>              Use the cells of a PBM or PGM image or a text file as the shape
+  --density <fraction>       Chance of each cell being marked
+  --difficulty <score>       Difficulty score to aim for
+  --candidates <count>       Levels to pick from when aiming for a difficulty
+  --modifiers                Add {n} and -n- modifiers to hints
+  --no-around                Don't show counts on marked cells
+  --no-lines                 Don't add line hints
+  --prune                    Remove hints the level can be solved without
+  -o, --output <file>        Where to write the level, standard output by default
+
+Options for validate:
+  --strict                   Fail on warnings as well as errors
+
+Options for render:
+  --solution                 Draw every cell revealed
+  --scale <pixels>           Size of a cell
+  --orientation <name>       flat or pointy topped cells, flat by default (SVG only)
+  --theme <name or file>     dark, high-contrast, colorblind, print or a theme file
+  --png                      Draw a PNG image, also picked when the output ends in .png
+  -o, --output <file>        Where to write the image, standard output by default
+
+Exits with 1 when a level can't be solved or isn't unique, and 2 on errors.";
+
+/// Options taking a value.
+const VALUE_OPTIONS: [&str; 14] = [
+    "--format",
+    "--from",
+    "--to",
+    "--output",
+    "-o",
+    "--seed",
+    "--shape",
+    "--mask",
+    "--density",
+    "--difficulty",
+    "--candidates",
+    "--scale",
+    "--orientation",
+    "--theme",
+];
+
+/// Options without a value.
+const FLAGS: [&str; 7] = [
+    "--modifiers",
+    "--no-around",
+    "--no-lines",
+    "--prune",
+    "--strict",
+    "--solution",
+    "--png",
+];
+
+/// The options each command takes.
+const SOLVE_OPTIONS: &[&str] = &["--format"];
+const VALIDATE_OPTIONS: &[&str] = &["--format", "--strict"];
+const CHECK_OPTIONS: &[&str] = &["--format"];
+const CONVERT_OPTIONS: &[&str] = &["--format", "--from", "--to"];
+const GENERATE_OPTIONS: &[&str] = &[
+    "--format",
+    "--to",
+    "--output",
+    "-o",
+    "--seed",
+    "--shape",
+    "--mask",
+    "--density",
+    "--difficulty",
+    "--candidates",
+    "--modifiers",
+    "--no-around",
+    "--no-lines",
+    "--prune",
+];
+const RENDER_OPTIONS: &[&str] = &[
+    "--format",
+    "--output",
+    "-o",
+    "--solution",
+    "--scale",
+    "--orientation",
+    "--theme",
+    "--png",
+];
+
+/// Runs a command, returning whether its checks passed.
+type Command = fn(&Arguments) -> Result<bool, String>;
+
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Arguments {
+    /// Splits the arguments of `command`, which only takes the options in `allowed`.
+    fn parse(command: &str, args: &[String], allowed: &[&str]) -> Result<Arguments, String> {
+        let mut arguments = Arguments {
+            positional: Vec::new(),
+            options: Vec::new(),
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg.starts_with('-') && arg != "-" {
+                let value = if VALUE_OPTIONS.contains(&arg.as_str()) {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("{} needs a value", arg))?;
+                    Some(value.clone())
+                } else if FLAGS.contains(&arg.as_str()) {
+                    None
+                } else {
+                    return Err(format!("unknown option: {}\n\n{}", arg, USAGE));
+                };
+
+                if !allowed.contains(&arg.as_str()) {
+                    return Err(format!("{} doesn't take {}\n\n{}", command, arg, USAGE));
+                }
+
+                arguments.options.push((arg.clone(), value));
+            } else {
+                arguments.positional.push(arg.clone());
+            }
+        }
+
+        Ok(arguments)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, names: &[&str]) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| names.contains(&option.as_str()))
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn parsed<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.value(&[name])
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", name, value))
+            })
+            .transpose()
+    }
+
+    fn format(&self, names: &[&str]) -> Result<Option<Format>, String> {
+        self.value(names)
+            .map(|name| Format::from_name(name).ok_or_else(|| format!("unknown format: {}", name)))
+            .transpose()
+    }
+
+    fn expect_positional(&self, count: usize) -> Result<(), String> {
+        if self.positional.len() == count {
+            Ok(())
+        } else {
+            Err(format!("expected {} arguments\n\n{}", count, USAGE))
+        }
+    }
+}
+
+fn format_for(path: &str, given: Option<Format>) -> Result<Format, String> {
+    match given {
+        Some(format) => Ok(format),
+        None if path == "-" => Ok(Format::Native),
+        None => Format::from_path(Path::new(path))
+            .ok_or_else(|| format!("can't tell the format of {}, use --format", path)),
+    }
+}
+
+fn read_input(path: &str, format: Option<Format>) -> Result<Level, String> {
+    let format = format_for(path, format)?;
+
+    let text = if path == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("couldn't read standard input: {}", error))?;
+        text
+    } else {
+        fs::read_to_string(path).map_err(|error| format!("couldn't read {}: {}", path, error))?
+    };
+
+    read_level(&text, format).map_err(|error| format!("{}: {}", path, error))
+}
+
+fn write_output(path: Option<&str>, contents: impl AsRef<[u8]>) -> Result<(), String> {
+    match path {
+        None | Some("-") => io::stdout()
+            .write_all(contents.as_ref())
+            .map_err(|error| format!("couldn't write standard output: {}", error)),
+        Some(path) => {
+            fs::write(path, contents).map_err(|error| format!("couldn't write {}: {}", path, error))
+        }
+    }
+}
+
+fn format_position(position: Axial) -> String {
+    format!("({}, {})", position.x, position.y)
+}
+
+fn format_hint(hint: &HintSource) -> String {
+    match hint {
+        HintSource::Neighbors(position) => format!("neighbors of {}", format_position(*position)),
+        HintSource::Around(position) => format!("around {}", format_position(*position)),
+        HintSource::Line(position) => format!("line at {}", format_position(*position)),
+        HintSource::Total => "total".to_string(),
+    }
+}
+
+fn solve_command(arguments: &Arguments) -> Result<bool, String> {
+    arguments.expect_positional(1)?;
+
+    let level = read_input(&arguments.positional[0], arguments.format(&["--format"])?)?;
+    let trace = solve(&level);
+
+    for (index, step) in trace.steps.iter().enumerate() {
+        println!("Step {} ({:?}):", index + 1, step.technique);
+
+        for deduction in &step.deductions {
+            let hints: Vec<String> = deduction.hints.iter().map(format_hint).collect();
+
+            println!(
+                "  {} is {}, from {}",
+                format_position(deduction.cell),
+                if deduction.marked { "marked" } else { "empty" },
+                hints.join(", ")
+            );
+        }
+    }
+
+    if trace.is_solved() {
+        println!("Solved in {} steps.", trace.steps.len());
+    } else {
+        let stuck: Vec<String> = trace.stuck.iter().copied().map(format_position).collect();
+        println!("Stuck with {} cells left: {}", stuck.len(), stuck.join(" "));
+    }
+
+    Ok(trace.is_solved())
+}
+
+fn read_levels(arguments: &Arguments) -> Result<Vec<Level>, String> {
+    if arguments.positional.is_empty() {
+        return Err(format!("expected at least one level\n\n{}", USAGE));
+    }
+
+    let format = arguments.format(&["--format"])?;
+
+    arguments
+        .positional
+        .iter()
+        .map(|path| read_input(path, format))
+        .collect()
+}
+
+fn validate_command(arguments: &Arguments) -> Result<bool, String> {
+    let levels = read_levels(arguments)?;
+
+    let failing = if arguments.flag("--strict") {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let mut all_passed = true;
+
+    for (path, level) in arguments.positional.iter().zip(&levels) {
+        let diagnostics = validate(level);
+
+        if diagnostics.is_empty() {
+            println!("{}: ok", path);
+        }
+
+        for diagnostic in diagnostics {
+            if diagnostic.severity() >= failing {
+                all_passed = false;
+            }
+
+            println!("{}: {}", path, diagnostic);
+        }
+    }
+
+    Ok(all_passed)
+}
+
+fn check_command(arguments: &Arguments) -> Result<bool, String> {
+    let levels = read_levels(arguments)?;
+
+    let mut all_passed = true;
+
+    for ((path, level), analysis) in arguments
+        .positional
+        .iter()
+        .zip(&levels)
+        .zip(analyze_levels(&levels))
+    {
+        let mut problems: Vec<String> = validate(level)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+
+        if let Uniqueness::Ambiguous(alternative) = &analysis.uniqueness {
+            let differing: Vec<String> = alternative
+                .differing
+                .iter()
+                .copied()
+                .map(format_position)
+                .collect();
+
+            problems.push(format!(
+                "has another solution differing at {}",
+                differing.join(" ")
+            ));
+        }
+
+        if !analysis.difficulty.solved {
+            problems.push("can't be solved without guessing".to_string());
+        }
+
+        if problems.is_empty() {
+            println!(
+                "{}: ok, difficulty {:.1} in {} steps",
+                path, analysis.difficulty.score, analysis.difficulty.steps
+            );
+        } else {
+            all_passed = false;
+            println!("{}: {}", path, problems.join(", "));
+        }
+    }
+
+    Ok(all_passed)
+}
+
+fn convert_command(arguments: &Arguments) -> Result<bool, String> {
+    arguments.expect_positional(2)?;
+
+    let (input, output) = (&arguments.positional[0], &arguments.positional[1]);
+
+    let level = read_input(input, arguments.format(&["--from", "--format"])?)?;
+    let format = format_for(output, arguments.format(&["--to"])?)?;
+
+    let text = write_level(&level, format).map_err(|error| format!("{}: {}", output, error))?;
+    write_output(Some(output), &text)?;
+
+    Ok(true)
+}
+
+fn parse_shape(text: &str) -> Result<Shape, String> {
+    let invalid = || format!("invalid shape: {}", text);
+
+    let (name, size) = text.split_once(':').ok_or_else(invalid)?;
+
+    let dimensions = || -> Result<(u32, u32), String> {
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        Ok((
+            width.parse().map_err(|_| invalid())?,
+            height.parse().map_err(|_| invalid())?,
+        ))
+    };
+
+    let single = || size.parse::<u32>().map_err(|_| invalid());
+
+    match name {
+        "hexagon" => Ok(Shape::Hexagon { radius: single()? }),
+        "triangle" => Ok(Shape::Triangle { size: single()? }),
+        "rhombus" => {
+            let (width, height) = dimensions()?;
+            Ok(Shape::Rhombus { width, height })
+        }
+        "rectangle" => {
+            let (width, height) = dimensions()?;
+            Ok(Shape::Rectangle { width, height })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn generate_command(arguments: &Arguments) -> Result<bool, String> {
+    arguments.expect_positional(0)?;
+
+    let mut params = GeneratorParams::default();
+
+    if let Some(shape) = arguments.value(&["--shape"]) {
+        params.shape = parse_shape(shape)?;
+    }
+
+    if let Some(mask) = arguments.value(&["--mask"]) {
+        params.shape = Shape::Mask(Mask::load(mask)?);
+    }
+
+    if let Some(density) = arguments.parsed::<f64>("--density")? {
+        if !(0.0..=1.0).contains(&density) {
+            return Err(format!("--density must be from 0 to 1, not {}", density));
+        }
+
+        params.marked_density = density;
+    }
+
+    if let Some(candidates) = arguments.parsed("--candidates")? {
+        params.candidates = candidates;
+    }
+
+    if let Some(difficulty) = arguments.parsed::<f32>("--difficulty")? {
+        if !(difficulty.is_finite() && difficulty >= 0.0) {
+            return Err(format!(
+                "--difficulty can't be less than 0, not {}",
+                difficulty
+            ));
+        }
+
+        params.target_difficulty = Some(difficulty);
+    }
+
+    params.hint_kinds.modifiers = arguments.flag("--modifiers");
+    params.hint_kinds.around = !arguments.flag("--no-around");
+    params.hint_kinds.lines = !arguments.flag("--no-lines");
+
+    let seed = match arguments.parsed("--seed")? {
+        Some(seed) => seed,
+        None => {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_nanos() as u64)
+                .unwrap_or(0);
+
+            eprintln!("Generating with seed {}", seed);
+            seed
+        }
+    };
+
+    let mut level = generate(seed, &params);
+
+    if arguments.flag("--prune") {
+        prune(&mut level).map_err(|_| "the generated level can't be pruned".to_string())?;
+    }
+
+    let output = arguments.value(&["--output", "-o"]);
+    let format = format_for(
+        output.unwrap_or("-"),
+        arguments.format(&["--to", "--format"])?,
+    )?;
+
+    let text = write_level(&level, format).map_err(|error| error.to_string())?;
+    write_output(output, &text)?;
+
+    Ok(true)
+}
+
+fn render_command(arguments: &Arguments) -> Result<bool, String> {
+    arguments.expect_positional(1)?;
+
+    let level = read_input(&arguments.positional[0], arguments.format(&["--format"])?)?;
+
+    let mut options = SvgOptions {
+        show_solution: arguments.flag("--solution"),
+        ..SvgOptions::default()
+    };
+
+    if let Some(scale) = arguments.parsed::<f32>("--scale")? {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(format!("--scale must be more than 0, not {}", scale));
+        }
+
+        options.scale = scale;
+    }
+
+    if let Some(name) = arguments.value(&["--orientation"]) {
+        options.orientation =
+            Orientation::from_name(name).ok_or_else(|| format!("unknown orientation: {}", name))?;
+    }
+
+    if let Some(name) = arguments.value(&["--theme"]) {
+        options.style = SvgStyle::from_theme(Theme::load(name)?);
+    }
+
+    let output = arguments.value(&["--output", "-o"]);
+
+    if arguments.flag("--png") || matches!(output, Some(path) if path.ends_with(".png")) {
+        let scene = level_scene(
+            &level,
+            options.scale,
+            options.show_solution,
+            &options.style.theme,
+        );
+        let image =
+            rasterize(&scene).map_err(|error| format!("couldn't draw the level: {}", error))?;
+        write_output(output, image.to_png())?;
+    } else {
+        write_output(output, render_svg(&level, &options))?;
+    }
+
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let (run, options): (Command, _) = match command {
+        "solve" => (solve_command, SOLVE_OPTIONS),
+        "validate" => (validate_command, VALIDATE_OPTIONS),
+        "check" => (check_command, CHECK_OPTIONS),
+        "convert" => (convert_command, CONVERT_OPTIONS),
+        "generate" => (generate_command, GENERATE_OPTIONS),
+        "render" => (render_command, RENDER_OPTIONS),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprintln!("unknown command: {}\n\n{}", command, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match Arguments::parse(command, &args[1..], options).and_then(|arguments| run(&arguments)) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    env, fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    generator::{generate, GeneratorParams, HintKinds},
+    level::Level,
+    prune::prune,
+    rng::RngVersion,
+    shapes::Shape,
+};
+
+/// Bumped whenever the daily level for a date changes, so old and new results aren't mixed up.
+const DAILY_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The current date in UTC, so everyone gets the same level at the same time.
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+
+        Date::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Converts days since 1970-01-01 to a date in the proleptic Gregorian calendar.
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    fn from_days(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Date {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    /// Parses a date written as `YYYY-MM-DD`.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.trim().splitn(3, '-');
+
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+
+        if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) {
+            Some(Date { year, month, day })
+        } else {
+            None
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// FNV-1a over the date and the daily version, which unlike `std`'s hashers is guaranteed to
+/// stay the same.
+pub fn daily_seed(date: Date) -> u64 {
+    format!("opencells-daily-{}-{}", DAILY_VERSION, date)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The level everyone plays on the given date.
+pub fn daily_level(date: Date) -> Level {
+    // Spelled out rather than taken from `GeneratorParams::default()`, as changing any of these
+    // changes every daily level.
+    let params = GeneratorParams {
+        shape: Shape::Hexagon { radius: 4 },
+        marked_density: 0.35,
+        hint_kinds: HintKinds {
+            neighbors: true,
+            around: true,
+            lines: true,
+            modifiers: true,
+        },
+        target_difficulty: Some(40.0),
+        candidates: 8,
+        rng_version: RngVersion::V1,
+    };
+
+    let mut level = generate(daily_seed(date), &params);
+    prune(&mut level).expect("generated levels can be solved without guessing");
+    level
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyResult {
+    pub date: Date,
+    pub mistakes: u32,
+    pub seconds: u64,
+}
+
+/// Where results are kept, one tab separated line per day.
+fn results_path() -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(data_dir.join("opencells").join("daily.tsv"))
+}
+
+/// Reads every stored result, skipping lines that can't be parsed.
+pub fn load_results() -> io::Result<Vec<DailyResult>> {
+    let path = match results_path() {
+        Some(path) => path,
+        None => return Ok(Vec::new()),
+    };
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let results = text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+
+            Some(DailyResult {
+                date: Date::parse(fields.next()?)?,
+                mistakes: fields.next()?.parse().ok()?,
+                seconds: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Stores a result, unless the day already has one. Only the first run of a day counts, so
+/// returns whether the result was stored.
+pub fn record_result(result: DailyResult) -> io::Result<bool> {
+    if load_results()?
+        .iter()
+        .any(|existing| existing.date == result.date)
+    {
+        return Ok(false);
+    }
+
+    let path = results_path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "no directory to store results in")
+    })?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{}\t{}\t{}",
+        result.date, result.mistakes, result.seconds
+    )?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checks_month_lengths() {
+        assert_eq!(
+            Date::parse("2024-02-29"),
+            Some(Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            })
+        );
+        assert!(Date::parse("2000-02-29").is_some());
+        assert!(Date::parse("2023-12-31").is_some());
+
+        for text in &[
+            "2023-02-29",
+            "1900-02-29",
+            "2024-02-30",
+            "2024-02-31",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-01-00",
+            "2024-01",
+        ] {
+            assert_eq!(Date::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn days_count_from_1970() {
+        let date = |days| Date::from_days(days).to_string();
+
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(19_782), "2024-02-29");
+        assert_eq!(date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn seed_stays_the_same() {
+        // Changing this breaks every saved daily result, bump `DAILY_VERSION` instead.
+        assert_eq!(DAILY_VERSION, 1);
+        assert_eq!(
+            daily_seed(Date::parse("2024-02-29").unwrap()),
+            0xee2e_b19a_6547_c78a
+        );
+    }
+}
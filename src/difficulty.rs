@@ -0,0 +1,125 @@
+use crate::{
+    level::Level,
+    solver::{solve, Technique, Trace},
+};
+
+#[derive(Debug, Clone)]
+pub struct Difficulty {
+    pub steps: usize,
+    pub hardest_technique: Option<Technique>,
+    /// The largest number of hints combined for a single deduction.
+    pub most_combined_hints: usize,
+    /// The largest number of cells that were deducible at the same time.
+    pub widest_frontier: usize,
+    /// The smallest number of cells that were deducible at the same time.
+    pub narrowest_frontier: usize,
+    pub solved: bool,
+    /// Sum of the step costs. Each step costs more the harder its technique and the more hints it
+    /// combines, and less the more moves the player could choose from.
+    pub score: f32,
+}
+
+impl Technique {
+    fn weight(self) -> f32 {
+        match self {
+            Technique::Single => 1.0,
+            Technique::Pair => 3.0,
+            Technique::Contradiction => 8.0,
+        }
+    }
+}
+
+pub fn rate_level(level: &Level) -> Difficulty {
+    rate_trace(&solve(level))
+}
+
+pub fn rate_trace(trace: &Trace) -> Difficulty {
+    let mut difficulty = Difficulty {
+        steps: trace.steps.len(),
+        hardest_technique: None,
+        most_combined_hints: 0,
+        widest_frontier: 0,
+        narrowest_frontier: 0,
+        solved: trace.is_solved(),
+        score: 0.0,
+    };
+
+    for step in &trace.steps {
+        let combined = step
+            .deductions
+            .iter()
+            .map(|deduction| deduction.hints.len())
+            .max()
+            .unwrap_or(0);
+        let frontier = step.deductions.len();
+
+        difficulty.hardest_technique = difficulty.hardest_technique.max(Some(step.technique));
+        difficulty.most_combined_hints = difficulty.most_combined_hints.max(combined);
+        difficulty.widest_frontier = difficulty.widest_frontier.max(frontier);
+        difficulty.narrowest_frontier = match difficulty.narrowest_frontier {
+            0 => frontier,
+            narrowest => narrowest.min(frontier),
+        };
+
+        let extra_hints = combined.saturating_sub(1) as f32;
+        difficulty.score +=
+            step.technique.weight() * (1.0 + 0.5 * extra_hints) / (frontier as f32).sqrt();
+    }
+
+    difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        solver::tests::{CONTRADICTION, PAIR, SINGLE},
+        test_levels::level,
+    };
+
+    #[test]
+    fn harder_techniques_rate_higher() {
+        let ratings: Vec<Difficulty> = [SINGLE, PAIR, CONTRADICTION]
+            .iter()
+            .map(|text| rate_level(&level(text)))
+            .collect();
+
+        assert!(ratings.iter().all(|rating| rating.solved));
+        assert_eq!(
+            ratings
+                .iter()
+                .map(|rating| rating.hardest_technique)
+                .collect::<Vec<_>>(),
+            [
+                Some(Technique::Single),
+                Some(Technique::Pair),
+                Some(Technique::Contradiction)
+            ]
+        );
+        assert!(ratings[0].score < ratings[1].score);
+        assert!(ratings[1].score < ratings[2].score);
+    }
+
+    #[test]
+    fn rating_counts_steps_and_frontiers() {
+        let rating = rate_level(&level(SINGLE));
+
+        assert_eq!(rating.steps, 1);
+        assert_eq!(rating.most_combined_hints, 1);
+        assert_eq!((rating.widest_frontier, rating.narrowest_frontier), (2, 2));
+        assert!((rating.score - 1.0 / 2f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stuck_level_is_unsolved() {
+        let rating = rate_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+
+        assert!(!rating.solved);
+        assert_eq!(rating.hardest_technique, None);
+        assert_eq!(rating.score, 0.0);
+    }
+}
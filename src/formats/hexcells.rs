@@ -0,0 +1,221 @@
+//! A header, title, author and two lines of description, followed by 33 rows of 33 two character
+//! slots. The grid uses doubled rows: a cell's neighbors above and below are two rows away, and
+//! those in the next column are one row up or down.
+//!
+//! The first character of a slot is `o` for an empty cell, `x` for a marked cell (upper case if
+//! revealed at the start), `|`, `\` or `/` for a line hint pointing down, down-right or
+//! down-left, or `.` for nothing. The second is `+` for a plain count, `c` for `{n}`, `n` for `-n-`
+//! or `.` for no count.
+
+use super::FormatError;
+use crate::{
+    hexagon::Axial,
+    level::{CellState, Hex, HintModifier, Level, LineDirection, LineHint},
+};
+
+const HEADER: &str = "Hexcells level v1";
+const GRID_SIZE: i32 = 33;
+
+fn to_grid(position: Axial) -> (i32, i32) {
+    (position.x, 2 * position.y + position.x)
+}
+
+/// Where the cells and line hints of a level are in the grid before it's moved into the corner,
+/// and the column and row the corner is moved to.
+fn grid_slots(level: &Level) -> (Vec<(i32, i32)>, (i32, i32)) {
+    let slots: Vec<(i32, i32)> = level
+        .cells
+        .keys()
+        .chain(level.line_hints.keys())
+        .map(|position| to_grid(*position))
+        .collect();
+
+    let min_column = slots.iter().map(|slot| slot.0).min().unwrap_or(0);
+    let min_row = slots.iter().map(|slot| slot.1).min().unwrap_or(0);
+
+    // Shifting by an odd number of rows would move cells between grid columns.
+    let row_shift = min_row - (min_row - min_column).rem_euclid(2);
+
+    (slots, (min_column, row_shift))
+}
+
+fn in_grid((column, row): (i32, i32), (min_column, row_shift): (i32, i32)) -> bool {
+    column - min_column < GRID_SIZE && row - row_shift < GRID_SIZE
+}
+
+pub fn fits_grid(level: &Level) -> bool {
+    let (slots, corner) = grid_slots(level);
+    slots.iter().all(|&slot| in_grid(slot, corner))
+}
+
+fn from_grid(column: i32, row: i32) -> Axial {
+    Axial::new(column, (row - column).div_euclid(2))
+}
+
+pub fn read(text: &str) -> Result<Level, FormatError> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    if lines.first().map(|line| line.trim()) != Some(HEADER) {
+        return Err(FormatError::malformed(1, format!("expected `{}`", HEADER)));
+    }
+
+    if lines.len() < 5 + GRID_SIZE as usize {
+        return Err(FormatError::malformed(
+            lines.len(),
+            "expected a title, author, two lines of description and 33 rows",
+        ));
+    }
+
+    let mut level = Level::new();
+    level.info.title = lines[1].to_string();
+    level.info.author = lines[2].to_string();
+    level.info.description = format!("{}\n{}", lines[3], lines[4]).trim().to_string();
+
+    for (row, line) in lines[5..5 + GRID_SIZE as usize].iter().enumerate() {
+        let number = row + 6;
+        let slots: Vec<char> = line.chars().collect();
+
+        if slots.len() < 2 * GRID_SIZE as usize {
+            return Err(FormatError::malformed(number, "expected 66 characters"));
+        }
+
+        for column in 0..GRID_SIZE as usize {
+            let (kind, value) = (slots[2 * column], slots[2 * column + 1]);
+            let position = from_grid(column as i32, row as i32);
+
+            let (shows_number, modifier) = match value {
+                '.' => (false, HintModifier::Plain),
+                '+' => (true, HintModifier::Plain),
+                'c' => (true, HintModifier::Contiguous),
+                'n' => (true, HintModifier::Separated),
+                _ => {
+                    return Err(FormatError::malformed(
+                        number,
+                        format!("unknown hint `{}`", value),
+                    ))
+                }
+            };
+
+            let direction = match kind {
+                '|' => Some(LineDirection::Down),
+                '\\' => Some(LineDirection::DownRight),
+                '/' => Some(LineDirection::DownLeft),
+                _ => None,
+            };
+
+            if let Some(direction) = direction {
+                level.line_hints.insert(
+                    position,
+                    LineHint {
+                        direction,
+                        modifier,
+                    },
+                );
+
+                continue;
+            }
+
+            let hex = match kind {
+                '.' => continue,
+                'o' | 'O' => Hex::Empty {
+                    show_neighbor_count: shows_number,
+                    modifier,
+                },
+                'x' | 'X' => Hex::Marked {
+                    show_around: shows_number,
+                },
+                _ => {
+                    return Err(FormatError::malformed(
+                        number,
+                        format!("unknown cell `{}`", kind),
+                    ))
+                }
+            };
+
+            let mut cell = CellState::new(hex);
+            cell.start_revealed = kind.is_ascii_uppercase();
+            level.cells.insert(position, cell);
+        }
+    }
+
+    Ok(level)
+}
+
+pub fn write(level: &Level) -> Result<String, FormatError> {
+    let (_, corner) = grid_slots(level);
+    let (min_column, row_shift) = corner;
+
+    let mut grid = vec![['.', '.']; (GRID_SIZE * GRID_SIZE) as usize];
+
+    let index = |position: Axial| -> Result<usize, FormatError> {
+        let slot = to_grid(position);
+
+        if !in_grid(slot, corner) {
+            return Err(FormatError::TooLarge);
+        }
+
+        Ok(((slot.1 - row_shift) * GRID_SIZE + slot.0 - min_column) as usize)
+    };
+
+    let modifier_char = |modifier: HintModifier| match modifier {
+        HintModifier::Plain => '+',
+        HintModifier::Contiguous => 'c',
+        HintModifier::Separated => 'n',
+    };
+
+    for (position, cell) in &level.cells {
+        let (kind, value) = match cell.hex {
+            Hex::Empty {
+                show_neighbor_count,
+                modifier,
+            } => (
+                'o',
+                if show_neighbor_count {
+                    modifier_char(modifier)
+                } else {
+                    '.'
+                },
+            ),
+            Hex::Marked { show_around } => ('x', if show_around { '+' } else { '.' }),
+        };
+
+        let kind = if cell.start_revealed {
+            kind.to_ascii_uppercase()
+        } else {
+            kind
+        };
+
+        grid[index(*position)?] = [kind, value];
+    }
+
+    for (origin, hint) in &level.line_hints {
+        let kind = match hint.direction {
+            LineDirection::Down => '|',
+            LineDirection::DownRight => '\\',
+            LineDirection::DownLeft => '/',
+        };
+
+        grid[index(*origin)?] = [kind, modifier_char(hint.modifier)];
+    }
+
+    let info = &level.info;
+    let mut description = info.description.lines();
+
+    let mut text = [
+        HEADER,
+        info.title.lines().next().unwrap_or(""),
+        info.author.lines().next().unwrap_or(""),
+        description.next().unwrap_or(""),
+        description.next().unwrap_or(""),
+    ]
+    .join("\n");
+
+    for row in grid.chunks(GRID_SIZE as usize) {
+        text.push('\n');
+        text.extend(row.iter().flatten());
+    }
+
+    text.push('\n');
+
+    Ok(text)
+}
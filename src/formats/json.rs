@@ -0,0 +1,403 @@
+//! Just enough JSON for SixCells files.
+
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they were read or added.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => {
+                f.write_char('"')?;
+
+                for character in text.chars() {
+                    match character {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        character if (character as u32) < 0x20 => {
+                            write!(f, "\\u{:04x}", character as u32)?
+                        }
+                        character => f.write_char(character)?,
+                    }
+                }
+
+                f.write_char('"')
+            }
+            Json::Array(items) => {
+                f.write_char('[')?;
+
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}", item)?;
+                }
+
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}: {}", Json::String(name.clone()), value)?;
+                }
+
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Parses a document, returning the line of the first error.
+pub fn parse(text: &str) -> Result<Json, usize> {
+    let mut parser = Parser {
+        text: text.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+
+    let value = parser.value();
+    parser.skip_whitespace();
+
+    match value {
+        Some(value) if parser.position == parser.text.len() => Ok(value),
+        _ => Err(parser.line()),
+    }
+}
+
+/// How deeply arrays and objects may nest, so hostile input can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    /// Arrays and objects currently open.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn line(&self) -> usize {
+        let end = self.position.min(self.text.len());
+        1 + self.text[..end]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.text.get(self.position), Some(byte) if byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+
+        if self.text.get(self.position) == Some(&byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Option<Json> {
+        if self.text[self.position..].starts_with(keyword.as_bytes()) {
+            self.position += keyword.len();
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+
+        match self.text.get(self.position)? {
+            b'n' => self.keyword("null", Json::Null),
+            b't' => self.keyword("true", Json::Bool(true)),
+            b'f' => self.keyword("false", Json::Bool(false)),
+            b'"' => self.string().map(Json::String),
+            b'[' | b'{' if self.depth == MAX_DEPTH => None,
+            b'[' => {
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            }
+            b'{' => {
+                self.depth += 1;
+                let object = self.object();
+                self.depth -= 1;
+                object
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.position += 1;
+        let mut items = Vec::new();
+
+        if self.eat(b']') {
+            return Some(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            if self.eat(b']') {
+                return Some(Json::Array(items));
+            }
+
+            if !self.eat(b',') {
+                return None;
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.position += 1;
+        let mut members = Vec::new();
+
+        if self.eat(b'}') {
+            return Some(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let name = self.string()?;
+
+            if !self.eat(b':') {
+                return None;
+            }
+
+            members.push((name, self.value()?));
+
+            if self.eat(b'}') {
+                return Some(Json::Object(members));
+            }
+
+            if !self.eat(b',') {
+                return None;
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.position;
+
+        while matches!(
+            self.text.get(self.position),
+            Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+        ) {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.text[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+            .map(Json::Number)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.text.get(self.position) != Some(&b'"') {
+            return None;
+        }
+
+        self.position += 1;
+        let mut bytes = Vec::new();
+
+        loop {
+            match *self.text.get(self.position)? {
+                b'"' => break,
+                b'\\' => {
+                    self.position += 1;
+
+                    let escaped = match *self.text.get(self.position)? {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let code = self.hex_escape(self.position + 1)?;
+                            self.position += 4;
+
+                            // Code points past U+FFFF are escaped as a high and a low surrogate.
+                            let low = match self.text.get(self.position + 1..self.position + 3) {
+                                Some(b"\\u") => self.hex_escape(self.position + 3),
+                                _ => None,
+                            };
+
+                            match low {
+                                Some(low @ 0xdc00..=0xdfff) if (0xd800..0xdc00).contains(&code) => {
+                                    self.position += 6;
+                                    let code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                    char::from_u32(code).unwrap()
+                                }
+                                _ => char::from_u32(code).unwrap_or('\u{fffd}'),
+                            }
+                        }
+                        byte => byte as char,
+                    };
+
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+
+            self.position += 1;
+        }
+
+        self.position += 1;
+        String::from_utf8(bytes).ok()
+    }
+
+    /// The four hex digits of a `\u` escape starting at `position`.
+    fn hex_escape(&self, position: usize) -> Option<u32> {
+        let hex = self.text.get(position..position + 4)?;
+
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+
+        u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        let json = parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d"}} "#).unwrap();
+
+        assert_eq!(
+            json.get("a").and_then(Json::as_array),
+            Some(
+                &[
+                    Json::Number(1.0),
+                    Json::Number(-25.0),
+                    Json::Bool(true),
+                    Json::Null
+                ][..]
+            )
+        );
+        assert_eq!(
+            json.get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(Json::as_str),
+            Some("d")
+        );
+    }
+
+    #[test]
+    fn written_values_read_back() {
+        let json = Json::Object(vec![
+            (
+                "text".to_string(),
+                Json::String("a \"b\"\n\\ \u{1} é 😀".to_string()),
+            ),
+            (
+                "list".to_string(),
+                Json::Array(vec![Json::Number(0.5), Json::Null]),
+            ),
+        ]);
+
+        assert_eq!(parse(&json.to_string()), Ok(json));
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let text = |json: &str| parse(json).unwrap().as_str().unwrap().to_string();
+
+        assert_eq!(text(r#""\n\t\"\\\/""#), "\n\t\"\\/");
+        assert_eq!(text(r#""\u00e9\u4E2D""#), "é中");
+        assert_eq!(text(r#""\ud83d\ude00""#), "😀");
+        // Surrogates that aren't paired can't be decoded.
+        assert_eq!(text(r#""\ud83d""#), "\u{fffd}");
+        assert_eq!(text(r#""\ude00\ud83d""#), "\u{fffd}\u{fffd}");
+        assert_eq!(text(r#""\ud83d\u0041""#), "\u{fffd}A");
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        assert_eq!(parse("[1,\n2,\n]"), Err(3));
+        assert_eq!(parse("{\"a\" 1}"), Err(1));
+        assert_eq!(parse("\"\\u12\""), Err(1));
+        assert_eq!(parse("\"\\u+123\""), Err(1));
+        assert_eq!(parse("[1] 2"), Err(1));
+        assert_eq!(parse(""), Err(1));
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(parse(&nested(MAX_DEPTH + 1)), Err(1));
+        assert_eq!(parse(&"{\"a\":".repeat(100_000)), Err(1));
+    }
+}
@@ -0,0 +1,105 @@
+//! Reading and writing levels as text.
+
+mod hexcells;
+mod json;
+mod native;
+mod sixcells;
+
+use std::{fmt, path::Path};
+
+use crate::level::Level;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Our own line based format, which can hold any level.
+    Native,
+    /// The format of Hexcells Infinite's level editor, limited to a 33 by 33 grid.
+    Hexcells,
+    /// SixCells' JSON format.
+    SixCells,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Native, Format::Hexcells, Format::SixCells];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Native => "native",
+            Format::Hexcells => "hexcells",
+            Format::SixCells => "sixcells",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Native => "opencells",
+            Format::Hexcells => "hexcells",
+            Format::SixCells => "sixcells",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?;
+
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    Malformed {
+        line: usize,
+        reason: String,
+    },
+    /// The level doesn't fit within the limits of the format.
+    TooLarge,
+}
+
+impl FormatError {
+    fn malformed(line: usize, reason: impl Into<String>) -> FormatError {
+        FormatError::Malformed {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Malformed { line, reason } => write!(f, "line {}: {}", line, reason),
+            FormatError::TooLarge => write!(f, "the level doesn't fit in the format"),
+        }
+    }
+}
+
+/// Whether a level fits the 33 by 33 grid of the Hexcells format, without writing it.
+pub fn fits_hexcells_grid(level: &Level) -> bool {
+    hexcells::fits_grid(level)
+}
+
+pub fn read_level(text: &str, format: Format) -> Result<Level, FormatError> {
+    match format {
+        Format::Native => native::read(text),
+        Format::Hexcells => hexcells::read(text),
+        Format::SixCells => sixcells::read(text),
+    }
+}
+
+pub fn write_level(level: &Level, format: Format) -> Result<String, FormatError> {
+    match format {
+        Format::Native => Ok(native::write(level)),
+        Format::Hexcells => hexcells::write(level),
+        Format::SixCells => Ok(sixcells::write(level)),
+    }
+}
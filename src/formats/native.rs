@@ -0,0 +1,270 @@
+//! One item per line, for example:
+//!
+//! ```text
+//! OpenCells level v1
+//! title Example
+//! cell 0 0 empty number contiguous revealed
+//! cell 0 1 marked number
+//! line 0 -1 down separated
+//! ```
+//!
+//! Cells are `cell <q> <r> <empty|marked>` followed by flags: `number` shows the cell's count,
+//! `contiguous` or `separated` sets the modifier of an empty cell's count and `revealed` reveals
+//! the cell at the start. Line hints are `line <q> <r> <direction>` with an optional modifier.
+//! Empty lines and lines starting with `#` are ignored.
+
+use std::fmt::Write;
+
+use super::FormatError;
+use crate::{
+    hexagon::Axial,
+    level::{CellState, Hex, HintModifier, Level, LineDirection, LineHint},
+};
+
+const HEADER: &str = "OpenCells level v1";
+
+fn direction_name(direction: LineDirection) -> &'static str {
+    match direction {
+        LineDirection::Down => "down",
+        LineDirection::DownRight => "down-right",
+        LineDirection::DownLeft => "down-left",
+    }
+}
+
+fn modifier_name(modifier: HintModifier) -> Option<&'static str> {
+    match modifier {
+        HintModifier::Plain => None,
+        HintModifier::Contiguous => Some("contiguous"),
+        HintModifier::Separated => Some("separated"),
+    }
+}
+
+fn modifier_from_name(name: &str) -> Option<HintModifier> {
+    match name {
+        "contiguous" => Some(HintModifier::Contiguous),
+        "separated" => Some(HintModifier::Separated),
+        _ => None,
+    }
+}
+
+pub fn read(text: &str) -> Result<Level, FormatError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+
+    match lines.next() {
+        Some((_, header)) if header.trim() == HEADER => {}
+        _ => return Err(FormatError::malformed(1, format!("expected `{}`", HEADER))),
+    }
+
+    let mut level = Level::new();
+    let mut description = Vec::new();
+
+    for (number, line) in lines {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, rest) = match line.find(' ') {
+            Some(space) => (&line[..space], line[space + 1..].trim()),
+            None => (line, ""),
+        };
+
+        match keyword {
+            "title" => level.info.title = rest.to_string(),
+            "author" => level.info.author = rest.to_string(),
+            "description" => description.push(rest),
+            "cell" => {
+                let mut words = rest.split_whitespace();
+                let position = read_position(&mut words, number)?;
+
+                let kind = words
+                    .next()
+                    .ok_or_else(|| FormatError::malformed(number, "missing cell kind"))?;
+
+                let mut shows_number = false;
+                let mut modifier = HintModifier::Plain;
+                let mut revealed = false;
+
+                for flag in words {
+                    match flag {
+                        "number" => shows_number = true,
+                        "revealed" => revealed = true,
+                        _ => match modifier_from_name(flag) {
+                            Some(flag_modifier) if kind == "empty" => modifier = flag_modifier,
+                            _ => {
+                                return Err(FormatError::malformed(
+                                    number,
+                                    format!("unknown cell flag `{}`", flag),
+                                ))
+                            }
+                        },
+                    }
+                }
+
+                let hex = match kind {
+                    "empty" => Hex::Empty {
+                        show_neighbor_count: shows_number,
+                        modifier,
+                    },
+                    "marked" => Hex::Marked {
+                        show_around: shows_number,
+                    },
+                    _ => {
+                        return Err(FormatError::malformed(
+                            number,
+                            format!("unknown cell kind `{}`", kind),
+                        ))
+                    }
+                };
+
+                let mut cell = CellState::new(hex);
+                cell.start_revealed = revealed;
+                level.cells.insert(position, cell);
+            }
+            "line" => {
+                let mut words = rest.split_whitespace();
+                let position = read_position(&mut words, number)?;
+
+                let direction = words
+                    .next()
+                    .and_then(|name| {
+                        LineDirection::ALL
+                            .iter()
+                            .copied()
+                            .find(|direction| direction_name(*direction) == name)
+                    })
+                    .ok_or_else(|| {
+                        FormatError::malformed(number, "missing or unknown direction")
+                    })?;
+
+                let modifier = match words.next() {
+                    Some(name) => modifier_from_name(name).ok_or_else(|| {
+                        FormatError::malformed(number, format!("unknown modifier `{}`", name))
+                    })?,
+                    None => HintModifier::Plain,
+                };
+
+                level.line_hints.insert(
+                    position,
+                    LineHint {
+                        direction,
+                        modifier,
+                    },
+                );
+            }
+            _ => {
+                return Err(FormatError::malformed(
+                    number,
+                    format!("unknown item `{}`", keyword),
+                ))
+            }
+        }
+    }
+
+    level.info.description = description.join("\n");
+
+    Ok(level)
+}
+
+fn read_position<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+    line: usize,
+) -> Result<Axial, FormatError> {
+    let mut coordinate = || {
+        words
+            .next()
+            .and_then(|word| word.parse().ok())
+            .ok_or_else(|| FormatError::malformed(line, "expected a coordinate"))
+    };
+
+    let q = coordinate()?;
+    let r = coordinate()?;
+
+    Ok(Axial::new(q, r))
+}
+
+pub fn write(level: &Level) -> String {
+    let mut text = String::new();
+
+    writeln!(text, "{}", HEADER).unwrap();
+
+    let info = &level.info;
+
+    if !info.title.is_empty() {
+        writeln!(text, "title {}", info.title.replace('\n', " ")).unwrap();
+    }
+
+    if !info.author.is_empty() {
+        writeln!(text, "author {}", info.author.replace('\n', " ")).unwrap();
+    }
+
+    if !info.description.is_empty() {
+        for line in info.description.lines() {
+            writeln!(text, "description {}", line).unwrap();
+        }
+    }
+
+    for position in level.sorted_positions() {
+        let cell = &level.cells[&position];
+
+        write!(text, "cell {} {}", position.x, position.y).unwrap();
+
+        match cell.hex {
+            Hex::Empty {
+                show_neighbor_count,
+                modifier,
+            } => {
+                text.push_str(" empty");
+
+                if show_neighbor_count {
+                    text.push_str(" number");
+                }
+
+                if let Some(name) = modifier_name(modifier) {
+                    write!(text, " {}", name).unwrap();
+                }
+            }
+            Hex::Marked { show_around } => {
+                text.push_str(" marked");
+
+                if show_around {
+                    text.push_str(" number");
+                }
+            }
+        }
+
+        if cell.start_revealed {
+            text.push_str(" revealed");
+        }
+
+        text.push('\n');
+    }
+
+    let mut origins: Vec<&Axial> = level.line_hints.keys().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    for origin in origins {
+        let hint = level.line_hints[origin];
+
+        write!(
+            text,
+            "line {} {} {}",
+            origin.x,
+            origin.y,
+            direction_name(hint.direction)
+        )
+        .unwrap();
+
+        if let Some(name) = modifier_name(hint.modifier) {
+            write!(text, " {}", name).unwrap();
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
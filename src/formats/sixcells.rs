@@ -0,0 +1,247 @@
+//! SixCells stores cells and line hints ("columns") as JSON objects with positions in units of
+//! the cell height, along with the cells each hint counts. Hints are rebuilt from the cell
+//! positions when reading, so levels with hand-picked neighbors can't be read.
+
+use std::collections::HashMap;
+
+use super::{
+    json::{self, Json},
+    FormatError,
+};
+use crate::{
+    hexagon::{axial_neighbors, axial_spiral, Axial},
+    level::{CellState, Hex, HintModifier, Level, LineDirection, LineHint},
+};
+
+const COLUMN_WIDTH: f64 = 0.866_025_403_784_438_6;
+
+fn to_point(position: Axial) -> (f64, f64) {
+    let row = 2 * position.y + position.x;
+    (position.x as f64 * COLUMN_WIDTH, row as f64 / 2.0)
+}
+
+fn from_point(x: f64, y: f64) -> Axial {
+    let column = (x / COLUMN_WIDTH).round() as i32;
+    let row = (y * 2.0).round() as i32;
+    Axial::new(column, (row - column).div_euclid(2))
+}
+
+/// Line hints are rotated clockwise from pointing down, in degrees.
+fn angle(direction: LineDirection) -> f64 {
+    match direction {
+        LineDirection::Down => 0.0,
+        LineDirection::DownRight => -60.0,
+        LineDirection::DownLeft => 60.0,
+    }
+}
+
+fn together(modifier: HintModifier) -> Option<Json> {
+    match modifier {
+        HintModifier::Plain => None,
+        HintModifier::Contiguous => Some(Json::Bool(true)),
+        HintModifier::Separated => Some(Json::Bool(false)),
+    }
+}
+
+fn modifier(object: &Json) -> HintModifier {
+    match object.get("together").and_then(Json::as_bool) {
+        Some(true) => HintModifier::Contiguous,
+        Some(false) => HintModifier::Separated,
+        None => HintModifier::Plain,
+    }
+}
+
+fn point(object: &Json) -> Option<Axial> {
+    let x = object.get("x")?.as_f64()?;
+    let y = object.get("y")?.as_f64()?;
+    Some(from_point(x, y))
+}
+
+pub fn read(text: &str) -> Result<Level, FormatError> {
+    let document =
+        json::parse(text).map_err(|line| FormatError::malformed(line, "invalid JSON"))?;
+    let malformed = |reason: &str| FormatError::malformed(1, reason);
+
+    let mut level = Level::new();
+
+    let text_of = |key: &str| {
+        document
+            .get(key)
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+
+    level.info.title = text_of("title");
+    level.info.author = text_of("author");
+    level.info.description = text_of("information");
+
+    let cells = document
+        .get("cells")
+        .and_then(Json::as_array)
+        .ok_or_else(|| malformed("missing cells"))?;
+
+    for cell in cells {
+        let position = point(cell).ok_or_else(|| malformed("cell without a position"))?;
+        let shows_number = cell.get("value").is_some();
+
+        let hex = match cell
+            .get("kind")
+            .and_then(Json::as_f64)
+            .map(|kind| kind as i64)
+        {
+            Some(0) => Hex::Empty {
+                show_neighbor_count: shows_number,
+                modifier: modifier(cell),
+            },
+            Some(1) => Hex::Marked {
+                show_around: shows_number,
+            },
+            _ => return Err(malformed("cell without a known kind")),
+        };
+
+        let mut state = CellState::new(hex);
+        state.start_revealed = cell.get("revealed").and_then(Json::as_bool) == Some(true);
+        level.cells.insert(position, state);
+    }
+
+    let columns = document
+        .get("columns")
+        .and_then(Json::as_array)
+        .unwrap_or(&[]);
+
+    for column in columns {
+        let origin = point(column).ok_or_else(|| malformed("column without a position"))?;
+        let column_angle = column.get("angle").and_then(Json::as_f64).unwrap_or(0.0);
+
+        let direction = LineDirection::ALL
+            .iter()
+            .copied()
+            .find(|direction| (angle(*direction) - column_angle).abs() < 1.0)
+            .ok_or_else(|| malformed("column with an unsupported angle"))?;
+
+        level.line_hints.insert(
+            origin,
+            LineHint {
+                direction,
+                modifier: modifier(column),
+            },
+        );
+    }
+
+    Ok(level)
+}
+
+pub fn write(level: &Level) -> String {
+    let positions = level.sorted_positions();
+
+    let id_of: HashMap<Axial, usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(id, position)| (*position, id))
+        .collect();
+
+    let ids = |cells: Vec<Axial>| {
+        Json::Array(
+            cells
+                .into_iter()
+                .filter_map(|cell| id_of.get(&cell))
+                .map(|id| Json::Number(*id as f64))
+                .collect(),
+        )
+    };
+
+    let cells = positions
+        .iter()
+        .map(|position| {
+            let cell = &level.cells[position];
+            let (x, y) = to_point(*position);
+
+            let (kind, neighbors, count, modifier) = match cell.hex {
+                Hex::Empty {
+                    show_neighbor_count,
+                    modifier,
+                } => (
+                    0.0,
+                    axial_neighbors(*position).collect(),
+                    Some(level.neighbor_count(*position)).filter(|_| show_neighbor_count),
+                    modifier,
+                ),
+                Hex::Marked { show_around } => (
+                    1.0,
+                    axial_spiral(*position, 2),
+                    Some(level.around_count(*position)).filter(|_| show_around),
+                    HintModifier::Plain,
+                ),
+            };
+
+            let mut members = vec![
+                ("kind".to_string(), Json::Number(kind)),
+                ("x".to_string(), Json::Number(x)),
+                ("y".to_string(), Json::Number(y)),
+                ("neighbors".to_string(), ids(neighbors)),
+            ];
+
+            if let Some(count) = count {
+                members.push(("value".to_string(), Json::Number(count as f64)));
+            }
+
+            if let Some(together) = together(modifier) {
+                members.push(("together".to_string(), together));
+            }
+
+            if cell.start_revealed {
+                members.push(("revealed".to_string(), Json::Bool(true)));
+            }
+
+            Json::Object(members)
+        })
+        .collect();
+
+    let mut origins: Vec<&Axial> = level.line_hints.keys().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    let columns = origins
+        .into_iter()
+        .map(|origin| {
+            let hint = level.line_hints[origin];
+            let (x, y) = to_point(*origin);
+
+            let mut members = vec![
+                ("x".to_string(), Json::Number(x)),
+                ("y".to_string(), Json::Number(y)),
+                ("angle".to_string(), Json::Number(angle(hint.direction))),
+                (
+                    "members".to_string(),
+                    ids(level.line_cells(*origin, hint.direction)),
+                ),
+                (
+                    "value".to_string(),
+                    Json::Number(level.line_count(*origin, hint.direction) as f64),
+                ),
+            ];
+
+            if let Some(together) = together(hint.modifier) {
+                members.push(("together".to_string(), together));
+            }
+
+            Json::Object(members)
+        })
+        .collect();
+
+    let info = &level.info;
+
+    let document = Json::Object(vec![
+        ("version".to_string(), Json::Number(1.0)),
+        ("title".to_string(), Json::String(info.title.clone())),
+        ("author".to_string(), Json::String(info.author.clone())),
+        (
+            "information".to_string(),
+            Json::String(info.description.clone()),
+        ),
+        ("cells".to_string(), Json::Array(cells)),
+        ("columns".to_string(), Json::Array(columns)),
+    ]);
+
+    format!("{}\n", document)
+}
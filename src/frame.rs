@@ -0,0 +1,101 @@
+//! Builds the scene drawn each frame from the game's state.
+
+use cgmath::Vector2;
+
+use opencells_rs::{
+    scene::Scene,
+    view::{view_scene, Board, EditorOverlays, Reveal, View},
+};
+
+use crate::game::{Drag, GameState};
+
+/// Builds the frame for the game's current state: the view of the level, then the frame stats
+/// and the interface on top.
+pub fn build_frame(state: &GameState) -> Scene {
+    let playing = state.is_playing();
+    let moving = matches!(state.drag, Some(Drag::Move { .. }));
+    let preview = state.drag_preview();
+
+    let reveal = if playing {
+        Reveal::Playing
+    } else {
+        Reveal::Editing
+    };
+
+    let board = Board {
+        offset: state.offset,
+        hovered: Some(state.cursor_hex_position),
+        selection: Some(&state.selection),
+        preview: if moving { &[] } else { &preview },
+        animations: Some(&state.animations),
+        ..Board::new(
+            &state.level,
+            &state.hints,
+            &state.theme,
+            state.scale,
+            reveal,
+        )
+    };
+
+    let editor = EditorOverlays {
+        analysis: state.live_solver.latest(),
+        analysis_current: state.live_solver.is_current(),
+        symmetry: state.symmetry,
+        symmetry_center: state.symmetry_center,
+        inspected: state
+            .inspected
+            .map(|position| (position, state.inspector_row)),
+        diagnostics: &state.diagnostics,
+    };
+
+    let view = View {
+        board,
+        size: state.viewport_size,
+        move_preview: if moving { &preview } else { &[] },
+        mistakes: state.mistakes(),
+        editor: if playing { None } else { Some(editor) },
+        status: state.status.message(),
+    };
+
+    let mut scene = view_scene(&view);
+
+    if state.frame_stats.visible {
+        add_frame_stats(&mut scene, state);
+    }
+
+    scene.rects.extend(state.ui.rects.iter().copied());
+    scene.overlay_texts.extend(state.ui.texts.iter().cloned());
+
+    scene
+}
+
+/// Shows how many frames were drawn in the last second, how long they took and how much of the
+/// second the game was busy, in the bottom right corner.
+fn add_frame_stats(scene: &mut Scene, state: &GameState) {
+    let lines = match state.frame_stats.summary() {
+        Some(summary) => vec![
+            format!("{} frames/s", summary.frames),
+            format!(
+                "frame {:.1} ms, slowest {:.1} ms",
+                summary.average_frame.as_secs_f32() * 1000.0,
+                summary.slowest_frame.as_secs_f32() * 1000.0
+            ),
+            format!("busy {:.1}% of wall time", summary.busy * 100.0),
+        ],
+        None => vec!["Measuring...".to_string()],
+    };
+
+    let line_height = 24.0;
+    let color = [0.6, 0.9, 0.6, 1.0];
+
+    for (index, line) in lines.iter().enumerate() {
+        let top = state.viewport_size.y - 16.0 - (lines.len() - index) as f32 * line_height;
+
+        scene.text(
+            line.as_str(),
+            Vector2::new(state.viewport_size.x - 320.0, top),
+            line_height * 0.8,
+            color,
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Measures how often frames are drawn and how much of the time the game is busy, shown as a
+//! debug overlay. An idle window should draw next to nothing.
+
+use std::time::{Duration, Instant};
+
+/// How long the numbers are collected for before they're shown.
+const PERIOD: Duration = Duration::from_secs(1);
+
+/// The numbers for the last finished period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSummary {
+    pub frames: u32,
+    /// Average and longest time spent building and drawing a frame.
+    pub average_frame: Duration,
+    pub slowest_frame: Duration,
+    /// Share of the period spent updating and drawing, from 0 to 1. This is wall-clock time on
+    /// the event loop, not CPU time, so it leaves out the background analysis and counts time
+    /// the thread was waiting on the driver.
+    pub busy: f32,
+}
+
+#[derive(Debug)]
+pub struct FrameStats {
+    pub visible: bool,
+
+    period_start: Instant,
+    frames: u32,
+    frame_time: Duration,
+    slowest_frame: Duration,
+    busy: Duration,
+
+    summary: Option<FrameSummary>,
+}
+
+impl FrameStats {
+    pub fn new(now: Instant) -> FrameStats {
+        FrameStats {
+            visible: false,
+            period_start: now,
+            frames: 0,
+            frame_time: Duration::from_secs(0),
+            slowest_frame: Duration::from_secs(0),
+            busy: Duration::from_secs(0),
+            summary: None,
+        }
+    }
+
+    /// Counts a frame that took `time` to build and draw.
+    pub fn frame(&mut self, time: Duration) {
+        self.frames += 1;
+        self.frame_time += time;
+        self.slowest_frame = self.slowest_frame.max(time);
+        self.busy += time;
+    }
+
+    /// Counts time spent updating the game outside of drawing.
+    pub fn busy(&mut self, time: Duration) {
+        self.busy += time;
+    }
+
+    /// Sums up the period once it's over, returning whether there's a new summary.
+    pub fn refresh(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.period_start);
+
+        if elapsed < PERIOD {
+            return false;
+        }
+
+        self.summary = Some(FrameSummary {
+            frames: self.frames,
+            average_frame: self.frame_time / self.frames.max(1),
+            slowest_frame: self.slowest_frame,
+            busy: self.busy.as_secs_f32() / elapsed.as_secs_f32(),
+        });
+
+        *self = FrameStats {
+            visible: self.visible,
+            summary: self.summary,
+            ..FrameStats::new(now)
+        };
+
+        true
+    }
+
+    /// When the current period is over.
+    pub fn next_refresh(&self) -> Instant {
+        self.period_start + PERIOD
+    }
+
+    pub fn summary(&self) -> Option<FrameSummary> {
+        self.summary
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        FrameStats::new(Instant::now())
+    }
+}
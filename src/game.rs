@@ -1,22 +1,151 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use cgmath::{Vector2, Zero};
 
-use crate::{
+use opencells_rs::{
+    animation::Animations,
+    daily::{record_result, DailyResult, Date},
+    difficulty::rate_level,
+    generator::GeneratorParams,
     hexagon::{
-        axial_to_cube, cube_to_axial, flat_hex_height, flat_hex_width, nearest_edge_hex,
-        pixel_to_flat_hex, spiral_ring, CUBE_DIRECTIONS,
+        axial_to_cube, cube_to_axial, flat_hex_height, flat_hex_width, pixel_to_flat_hex,
+        spiral_ring, Axial,
+    },
+    hints::HintCache,
+    inspector,
+    level::{CellState, Hex, HintModifier, Level},
+    live::LiveSolver,
+    play::{Guess, PlaySession},
+    prune::prune,
+    selection::{
+        delete, move_selection, rectangle, set_marked, set_start_revealed, Clipboard, SelectMode,
+        Selection,
     },
-    input::{HexKind, InputAction, InputState},
-    level::{CellState, Hex, Level},
+    shapes::{Mask, Shape},
+    symmetry::Symmetry,
+    theme::Theme,
+    uniqueness::{check_uniqueness, Uniqueness},
+    validate::{validate, Diagnostic},
+    worker::{LevelRequest, LevelWorker},
+};
+
+use crate::{
+    frame_stats::FrameStats,
+    input::{HexKind, InputAction, InputState, PointerButton},
+    menu,
+    status::Status,
+    ui::{Ui, UiInput},
 };
 
+pub struct PlayState {
+    pub session: PlaySession,
+    /// The date of the daily level being played, if it is one.
+    pub daily: Option<Date>,
+}
+
+pub enum Mode {
+    Edit,
+    Play(PlayState),
+}
+
+/// A drag with the primary button while editing, and the position it started at.
+#[derive(Debug, Clone, Copy)]
+pub enum Drag {
+    Select {
+        from: Axial,
+        mode: SelectMode,
+    },
+    /// Started on a selected cell, so it moves the selection.
+    Move {
+        from: Axial,
+    },
+}
+
+/// An action that throws away the level being edited, waiting for the player to ask again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    Generate,
+    Daily,
+}
+
+/// What an action changed in the level, so that only the affected hints are recounted.
+#[derive(Debug)]
+pub enum Change {
+    None,
+    /// Cells or line hints at these positions were added, removed or edited.
+    Positions(Vec<Axial>),
+    /// The level was replaced or changed all over.
+    Level,
+}
+
+impl Change {
+    fn merge(self, other: Change) -> Change {
+        match (self, other) {
+            (Change::Level, _) | (_, Change::Level) => Change::Level,
+            (Change::None, other) | (other, Change::None) => other,
+            (Change::Positions(mut positions), Change::Positions(other)) => {
+                positions.extend(other);
+                Change::Positions(positions)
+            }
+        }
+    }
+}
+
 pub struct GameState {
     pub level: Level,
+    /// Counts shown on the level's hints.
+    pub hints: HintCache,
+    pub mode: Mode,
 
     pub scale: f32,
     pub offset: Vector2<f32>,
 
-    pub nearest_edge: Vector2<f32>,
     pub cursor_hex_position: Vector2<i32>,
+    /// The mouse position and the size of the window, in pixels.
+    pub pointer: Vector2<f32>,
+    pub viewport_size: Vector2<f32>,
+    /// Physical pixels per logical pixel. Text is laid out in physical pixels, so a change
+    /// redraws the frame even when the logical size stays the same.
+    pub scale_factor: f64,
+
+    pub stamp_shapes: Vec<Shape>,
+    pub stamp_shape_index: usize,
+
+    /// Problems with the level, updated after each edit.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Solves the level in the background after each edit.
+    pub live_solver: LiveSolver,
+    /// Generates levels in the background, to replace the level once they're ready.
+    pub level_worker: LevelWorker,
+    /// Whether the level was edited since it was last replaced, so replacing it asks first.
+    pub edited: bool,
+    /// The replacement asked for by the last action, which goes ahead if it's asked for again.
+    pub unconfirmed: Option<Replacement>,
+
+    pub selection: Selection,
+    pub clipboard: Option<Clipboard>,
+    pub drag: Option<Drag>,
+
+    /// Repeats placing, clearing and revealing cells around `symmetry_center`.
+    pub symmetry: Symmetry,
+    pub symmetry_center: Axial,
+
+    /// The cell shown in the inspector: the selected cell if exactly one is selected, otherwise
+    /// the hovered one.
+    pub inspected: Option<Axial>,
+    /// The inspector row edited with the keyboard.
+    pub inspector_row: usize,
+
+    pub theme: Theme,
+    /// Cell animations, played over the level as it's drawn.
+    pub animations: Animations,
+    pub frame_stats: FrameStats,
+
+    /// The interface over the level, rebuilt each update.
+    pub ui: Ui,
+    pub menu_open: bool,
+    /// Reports the results of actions.
+    pub status: Status,
 }
 
 impl GameState {
@@ -27,100 +156,733 @@ impl GameState {
 
         GameState {
             level: Level::new(),
+            hints: HintCache::default(),
+            mode: Mode::Edit,
             scale,
             offset,
             cursor_hex_position: Vector2::zero(),
-            nearest_edge: Vector2::zero(),
+            pointer: Vector2::zero(),
+            viewport_size: Vector2::zero(),
+            scale_factor: 1.0,
+            stamp_shapes: vec![
+                Shape::Hexagon { radius: 2 },
+                Shape::Triangle { size: 4 },
+                Shape::Rhombus {
+                    width: 4,
+                    height: 3,
+                },
+                Shape::Rectangle {
+                    width: 5,
+                    height: 4,
+                },
+            ],
+            stamp_shape_index: 0,
+            diagnostics: Vec::new(),
+            live_solver: LiveSolver::new(),
+            level_worker: LevelWorker::new(),
+            edited: false,
+            unconfirmed: None,
+            selection: Selection::new(),
+            clipboard: None,
+            drag: None,
+            symmetry: Symmetry::Off,
+            symmetry_center: Vector2::zero(),
+            inspected: None,
+            inspector_row: 0,
+            theme: Theme::default(),
+            animations: Animations::new(),
+            frame_stats: FrameStats::default(),
+            ui: Ui::new(),
+            menu_open: false,
+            status: Status::default(),
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, Mode::Play(_))
+    }
+
+    pub fn mistakes(&self) -> u32 {
+        match &self.mode {
+            Mode::Play(play) => play.session.mistakes,
+            Mode::Edit => 0,
+        }
+    }
+
+    /// Properties of the inspected cell, empty if there isn't one.
+    pub fn inspected_properties(&self) -> &'static [inspector::Property] {
+        self.inspected
+            .and_then(|position| self.level.cells.get(&position))
+            .map(inspector::properties)
+            .unwrap_or(&[])
+    }
+
+    /// The cursor's position and its images under the current symmetry.
+    fn symmetric_cursor_positions(&self) -> Vec<Axial> {
+        self.symmetry
+            .images(self.symmetry_center, self.cursor_hex_position)
+    }
+
+    /// Positions the current drag would select, or where the selection would move to.
+    pub fn drag_preview(&self) -> Vec<Axial> {
+        match self.drag {
+            Some(Drag::Select { from, .. }) => {
+                rectangle(&self.level, from, self.cursor_hex_position)
+            }
+            Some(Drag::Move { from }) => {
+                let offset = self.cursor_hex_position - from;
+                self.selection
+                    .iter()
+                    .map(|position| position + offset)
+                    .collect()
+            }
+            None => Vec::new(),
         }
     }
+
+    fn start_playing(&mut self, daily: Option<Date>) {
+        self.drag = None;
+        self.animations.clear();
+        self.mode = Mode::Play(PlayState {
+            session: PlaySession::start(&self.level),
+            daily,
+        });
+    }
 }
 
-fn handle_input(state: &mut GameState, input_state: &mut InputState) {
-    input_state.mouse_position = input_state.absolute_mouse_position - state.offset;
+fn reveal_cell(state: &mut GameState, kind: HexKind) {
+    let play = match &mut state.mode {
+        Mode::Play(play) => play,
+        Mode::Edit => return,
+    };
+
+    let position = state.cursor_hex_position;
+    let guess = play
+        .session
+        .guess(&state.level, position, kind == HexKind::Marked);
 
-    state.cursor_hex_position = pixel_to_flat_hex(input_state.mouse_position, state.scale);
+    match guess {
+        Guess::Correct => state.animations.reveal(position),
+        Guess::Completed => {
+            state.animations.reveal(position);
+            state
+                .animations
+                .complete(position, state.level.cells.keys().copied());
+        }
+        Guess::Mistake => state.animations.mistake(position),
+        Guess::Ignored => {}
+    }
+
+    if guess != Guess::Completed {
+        return;
+    }
 
-    let nearest_edge = nearest_edge_hex(input_state.mouse_position, state.scale);
-    state.nearest_edge = nearest_edge;
+    let session = &play.session;
+    let seconds = session.elapsed().as_secs();
 
-    let mut invalidated = false;
+    let solved = format!(
+        "Solved in {} seconds with {} mistakes.",
+        seconds, session.mistakes
+    );
 
-    for action in input_state.action_queue.drain(..) {
-        match action {
-            InputAction::PlaceHex(kind) => {
+    let message = match play.daily {
+        Some(date) => {
+            let result = DailyResult {
+                date,
+                mistakes: session.mistakes,
+                seconds,
+            };
+
+            match record_result(result) {
+                Ok(true) => format!("{} Recorded the result for {}.", solved, date),
+                Ok(false) => format!("{} {} already has a result.", solved, date),
+                Err(error) => format!("{} Couldn't record the result: {}", solved, error),
+            }
+        }
+        None => solved,
+    };
+
+    state.status.show(message);
+}
+
+fn start_drag(state: &mut GameState, mode: SelectMode) {
+    let from = state.cursor_hex_position;
+
+    state.drag = Some(
+        if mode == SelectMode::Replace && state.selection.contains(from) {
+            Drag::Move { from }
+        } else {
+            Drag::Select { from, mode }
+        },
+    );
+}
+
+fn end_drag(state: &mut GameState) -> Change {
+    match state.drag.take() {
+        Some(Drag::Select { from, mode }) => {
+            let positions = rectangle(&state.level, from, state.cursor_hex_position);
+            state.selection.select(positions, mode);
+            Change::None
+        }
+        Some(Drag::Move { from }) => {
+            let offset = state.cursor_hex_position - from;
+
+            if offset.is_zero() {
+                return Change::None;
+            }
+
+            let mut positions: Vec<Axial> = state.selection.iter().collect();
+            move_selection(&mut state.level, &mut state.selection, offset);
+            positions.extend(state.selection.iter());
+
+            Change::Positions(positions)
+        }
+        None => Change::None,
+    }
+}
+
+/// Edits a property of the inspected cell, keeping it selected if it moves.
+fn change_property(state: &mut GameState, row: usize, forward: bool) -> Change {
+    let (position, property) = match (state.inspected, state.inspected_properties().get(row)) {
+        (Some(position), Some(property)) => (position, *property),
+        _ => return Change::None,
+    };
+
+    let moved_to = match inspector::change(&mut state.level, position, property, forward) {
+        Some(moved_to) => moved_to,
+        None => return Change::None,
+    };
+
+    if state.selection.contains(position) {
+        state.selection.select(Some(position), SelectMode::Remove);
+        state.selection.select(Some(moved_to), SelectMode::Add);
+    }
+
+    state.inspected = Some(moved_to);
+    state.inspector_row = row;
+    Change::Positions(vec![position, moved_to])
+}
+
+fn update_inspected(state: &mut GameState) {
+    let rows = state.inspected_properties().len();
+
+    let selected = if state.selection.len() == 1 {
+        state
+            .selection
+            .iter()
+            .find(|position| state.level.cells.contains_key(position))
+    } else {
+        None
+    };
+
+    if selected.is_some() {
+        state.inspected = selected;
+    } else if !inspector::contains(state.viewport_size.x, state.pointer, rows) {
+        // Keeps showing the hovered cell while the mouse is on its way to the panel.
+        let hovered = state.cursor_hex_position;
+        state.inspected = Some(hovered).filter(|position| state.level.cells.contains_key(position));
+    }
+
+    let rows = state.inspected_properties().len();
+    state.inspector_row = state.inspector_row.min(rows.saturating_sub(1));
+}
+
+/// Moves the cursor to a position relative to the level's origin, in pixels.
+pub fn move_cursor(state: &mut GameState, mouse_position: Vector2<f32>) {
+    state.cursor_hex_position = pixel_to_flat_hex(mouse_position, state.scale);
+}
+
+/// Whether the level can be replaced, asking for the action to be repeated first if that would
+/// throw away edits.
+fn confirm_replacement(state: &mut GameState, replacement: Replacement, confirmed: bool) -> bool {
+    if !state.edited || confirmed {
+        return true;
+    }
+
+    state.unconfirmed = Some(replacement);
+    state
+        .status
+        .show("The level has unsaved edits, do that again to replace it.");
+    false
+}
+
+/// Performs an action at the cursor, returning what it changed in the level.
+pub fn apply_action(state: &mut GameState, action: InputAction) -> Change {
+    let is_play_action = matches!(
+        action,
+        InputAction::PointerDown(..)
+            | InputAction::PointerUp(_)
+            | InputAction::TogglePlay
+            | InputAction::StartDaily
+            | InputAction::NextTheme
+            | InputAction::ToggleReduceMotion
+            | InputAction::ToggleFrameStats
+            | InputAction::ToggleMenu
+    );
+
+    // The level can't be edited while it's being played.
+    if state.is_playing() && !is_play_action {
+        return Change::None;
+    }
+
+    let mut change = Change::None;
+    // Any other action in between cancels a replacement waiting to be confirmed.
+    let unconfirmed = state.unconfirmed.take();
+
+    match action {
+        InputAction::PlaceHex(kind) if !state.selection.is_empty() => {
+            set_marked(&mut state.level, &state.selection, kind == HexKind::Marked);
+
+            change = Change::Positions(state.selection.iter().collect());
+        }
+        InputAction::PlaceHex(kind) => {
+            let positions = state.symmetric_cursor_positions();
+
+            for position in &positions {
                 state.level.cells.insert(
-                    state.cursor_hex_position,
+                    *position,
                     match kind {
                         HexKind::Empty => CellState::new(Hex::Empty {
                             show_neighbor_count: true,
+                            modifier: HintModifier::Plain,
                         }),
                         HexKind::Marked => CellState::new(Hex::Marked { show_around: false }),
                     },
                 );
+            }
+
+            change = Change::Positions(positions);
+        }
+        InputAction::ClearHex => {
+            let positions = state.symmetric_cursor_positions();
 
-                invalidated = true;
+            for position in &positions {
+                state.level.cells.remove(position);
             }
-            InputAction::ClearHex => {
-                state.level.cells.remove(&state.cursor_hex_position);
 
-                invalidated = true;
+            change = Change::Positions(positions);
+        }
+        InputAction::RingDebug => {
+            let mut coords = Vec::new();
+            spiral_ring(axial_to_cube(state.cursor_hex_position), 1, &mut coords);
+
+            let coords: Vec<Axial> = coords.drain(..).map(cube_to_axial).collect();
+
+            for coord in coords.iter().copied() {
+                state.level.cells.insert(
+                    coord,
+                    CellState::new(Hex::Empty {
+                        show_neighbor_count: false,
+                        modifier: HintModifier::Plain,
+                    }),
+                );
             }
-            InputAction::RingDebug => {
-                let mut coords = Vec::new();
-                spiral_ring(axial_to_cube(state.cursor_hex_position), 1, &mut coords);
 
-                for c in coords.drain(..) {
-                    let coord = cube_to_axial(c);
-                    state.level.cells.insert(
-                        coord,
-                        CellState::new(Hex::Empty {
-                            show_neighbor_count: false,
-                        }),
-                    );
+            change = Change::Positions(coords);
+        }
+        InputAction::ToggleRevealed if !state.selection.is_empty() => {
+            let all_revealed = state.selection.iter().all(|position| {
+                state
+                    .level
+                    .cells
+                    .get(&position)
+                    .map(|cell| cell.start_revealed)
+                    .unwrap_or(true)
+            });
+
+            set_start_revealed(&mut state.level, &state.selection, !all_revealed);
+
+            change = Change::Positions(state.selection.iter().collect());
+        }
+        InputAction::ToggleRevealed => {
+            let start_revealed = match state.level.cells.get(&state.cursor_hex_position) {
+                Some(cell) => !cell.start_revealed,
+                None => return Change::None,
+            };
+
+            let positions = state.symmetric_cursor_positions();
+
+            // The images follow the cell under the cursor, so they stay in sync.
+            for position in &positions {
+                if let Some(cell) = state.level.cells.get_mut(position) {
+                    cell.start_revealed = start_revealed;
                 }
+            }
+
+            change = Change::Positions(positions);
+        }
+        InputAction::PreviousProperty => {
+            state.inspector_row = state.inspector_row.saturating_sub(1);
+        }
+        InputAction::NextProperty => state.inspector_row += 1,
+        InputAction::ChangeProperty { forward } => {
+            change = change_property(state, state.inspector_row, forward);
+        }
+        InputAction::NextSymmetry => {
+            state.symmetry = state.symmetry.next();
+        }
+        InputAction::ToggleMenu => state.menu_open = !state.menu_open,
+        InputAction::ToggleFrameStats => {
+            state.frame_stats.visible = !state.frame_stats.visible;
+        }
+        InputAction::ToggleReduceMotion => {
+            state.animations.reduce_motion = !state.animations.reduce_motion;
+            state.animations.clear();
+
+            let setting = if state.animations.reduce_motion {
+                "off"
+            } else {
+                "on"
+            };
+            state.status.show(format!("Animations: {}", setting));
+        }
+        InputAction::NextTheme => {
+            state.theme = state.theme.next_built_in();
+            state.status.show(format!("Theme: {}", state.theme.name));
+        }
+        InputAction::SetSymmetryCenter => {
+            state.symmetry_center = state.cursor_hex_position;
+        }
+        InputAction::CheckUniqueness => match check_uniqueness(&state.level) {
+            Uniqueness::Unique => state.status.show("The level has a unique solution."),
+            Uniqueness::Ambiguous(alternative) => {
+                // Selecting the cells that differ shows where the other solution is.
+                state.status.show(format!(
+                    "The level has another solution, differing at the {} selected cells.",
+                    alternative.differing.len()
+                ));
+                state
+                    .selection
+                    .select(alternative.differing, SelectMode::Replace);
+            }
+        },
+        InputAction::RateDifficulty => {
+            let difficulty = rate_level(&state.level);
+            let message = match (difficulty.solved, difficulty.hardest_technique) {
+                (true, Some(technique)) => format!(
+                    "Difficulty {:.1}: {} steps, needs {}.",
+                    difficulty.score,
+                    difficulty.steps,
+                    technique.name()
+                ),
+                (true, None) => "The level is already solved.".to_string(),
+                (false, _) => format!(
+                    "The level can't be solved without guessing, stuck after {} steps.",
+                    difficulty.steps
+                ),
+            };
+            state.status.show(message);
+        }
+        InputAction::GenerateLevel => {
+            let confirmed = unconfirmed == Some(Replacement::Generate);
+
+            if confirm_replacement(state, Replacement::Generate, confirmed) {
+                let seed = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|time| time.as_nanos() as u64)
+                    .unwrap_or(0);
+
+                state.level_worker.submit(LevelRequest::Generate {
+                    seed,
+                    params: GeneratorParams::default(),
+                });
+                state.status.show("Generating a level...");
+            }
+        }
+        InputAction::StampShape => {
+            let shape = &state.stamp_shapes[state.stamp_shape_index];
+            let positions: Vec<Axial> = shape
+                .cells()
+                .into_iter()
+                .map(|offset| state.cursor_hex_position + offset)
+                .collect();
+
+            for position in &positions {
+                state.level.cells.entry(*position).or_insert_with(|| {
+                    CellState::new(Hex::Empty {
+                        show_neighbor_count: true,
+                        modifier: HintModifier::Plain,
+                    })
+                });
+            }
+
+            change = Change::Positions(positions);
+        }
+        InputAction::NextStampShape => {
+            state.stamp_shape_index = (state.stamp_shape_index + 1) % state.stamp_shapes.len();
+            let shape = state.stamp_shapes[state.stamp_shape_index].name();
+            state.status.show(format!("Stamping a {}.", shape));
+        }
+        InputAction::LoadMask(path) => match Mask::load(&path.to_string_lossy()) {
+            Ok(mask) => {
+                // A new mask takes the place of the last one.
+                let previous = state
+                    .stamp_shapes
+                    .iter()
+                    .position(|shape| matches!(shape, Shape::Mask(_)));
+                let index = match previous {
+                    Some(index) => {
+                        state.stamp_shapes[index] = Shape::Mask(mask);
+                        index
+                    }
+                    None => {
+                        state.stamp_shapes.push(Shape::Mask(mask));
+                        state.stamp_shapes.len() - 1
+                    }
+                };
 
-                invalidated = true;
+                state.stamp_shape_index = index;
+                state
+                    .status
+                    .show(format!("Stamping the mask from {}.", path.display()));
             }
-            InputAction::ToggleRevealed => {
-                let cell = state.level.cells.get_mut(&state.cursor_hex_position);
-                if let Some(cell) = cell {
-                    cell.start_revealed = !cell.start_revealed;
+            Err(error) => state.status.show(error),
+        },
+        InputAction::PruneLevel => match prune(&mut state.level) {
+            Ok(removed) => {
+                state.status.show(format!(
+                    "Removed {} hints and start reveals.",
+                    removed.len()
+                ));
+                change = Change::Level;
+            }
+            Err(unsolvable) => {
+                state.status.show(format!(
+                    "The level needs guessing, it gets stuck at the {} selected cells.",
+                    unsolvable.stuck.len()
+                ));
+                state
+                    .selection
+                    .select(unsolvable.stuck, SelectMode::Replace);
+            }
+        },
+        InputAction::PointerDown(button, mode) => match (state.is_playing(), button) {
+            (true, PointerButton::Primary) => reveal_cell(state, HexKind::Marked),
+            (true, PointerButton::Secondary) => reveal_cell(state, HexKind::Empty),
+            (false, button) => {
+                let rows = state.inspected_properties().len();
+                let forward = button == PointerButton::Primary;
+
+                match inspector::row_at(state.viewport_size.x, state.pointer, rows) {
+                    Some(row) => change = change_property(state, row, forward),
+                    None if forward => start_drag(state, mode),
+                    None => {}
                 }
             }
+        },
+        InputAction::PointerUp(PointerButton::Primary) => change = end_drag(state),
+        InputAction::PointerUp(PointerButton::Secondary) => {}
+        InputAction::FloodSelect(mode) => {
+            let cells = state.level.connected_cells(state.cursor_hex_position);
+            state.selection.select(cells, mode);
         }
-    }
+        InputAction::SelectAll => {
+            let positions = state
+                .level
+                .cells
+                .keys()
+                .chain(state.level.line_hints.keys());
+            state
+                .selection
+                .select(positions.copied(), SelectMode::Replace);
+        }
+        InputAction::ClearSelection => state.selection.clear(),
+        InputAction::DeleteSelection => {
+            delete(&mut state.level, &state.selection);
+
+            change = Change::Positions(state.selection.iter().collect());
+            state.selection.clear();
+        }
+        InputAction::Copy => {
+            state.clipboard = Clipboard::copy(&state.level, &state.selection);
+        }
+        InputAction::Paste => {
+            if let Some(clipboard) = &state.clipboard {
+                let pasted = clipboard.paste(&mut state.level, state.cursor_hex_position);
+                state
+                    .selection
+                    .select(pasted.iter().copied(), SelectMode::Replace);
+
+                change = Change::Positions(pasted);
+            }
+        }
+        InputAction::TogglePlay => {
+            if state.is_playing() {
+                state.mode = Mode::Edit;
+                state.animations.clear();
+            } else {
+                state.start_playing(None);
+            }
+        }
+        InputAction::StartDaily => {
+            let confirmed = unconfirmed == Some(Replacement::Daily);
+
+            if confirm_replacement(state, Replacement::Daily, confirmed) {
+                let date = Date::today();
 
-    if invalidated {
-        calculate_hints(state);
+                state.level_worker.submit(LevelRequest::Daily(date));
+                state
+                    .status
+                    .show(format!("Preparing the daily level for {}...", date));
+            }
+        }
     }
-}
 
-pub fn update(state: &mut GameState, input_state: &mut InputState) {
-    handle_input(state, input_state);
+    change
 }
 
-pub fn calculate_hints(state: &mut GameState) {
-    for (axial, cell) in state.level.cells.iter() {
-        match &cell.hex {
-            Hex::Empty {
-                show_neighbor_count: true,
-            } => {
-                let cube = axial_to_cube(*axial);
+/// How often frames are drawn while something is moving.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// How often a running analysis is checked for results.
+const SOLVER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Updates the game with the input since the last update, returning whether anything shown has
+/// changed and the frame needs drawing again.
+pub fn update(state: &mut GameState, input_state: &mut InputState) -> bool {
+    let now = Instant::now();
+    let mut dirty = !input_state.action_queue.is_empty()
+        || !input_state.typed.is_empty()
+        || state.pointer != input_state.absolute_mouse_position
+        || state.viewport_size != input_state.viewport_size
+        || state.scale_factor != input_state.scale_factor;
 
-                let mut neighbor_count = 0;
+    input_state.mouse_position = input_state.absolute_mouse_position - state.offset;
+    move_cursor(state, input_state.mouse_position);
 
-                for direction in &CUBE_DIRECTIONS {
-                    let neighbor_cube = cube + direction;
-                    let neighbor_axial = cube_to_axial(neighbor_cube);
+    state.pointer = input_state.absolute_mouse_position;
+    state.viewport_size = input_state.viewport_size;
+    state.scale_factor = input_state.scale_factor;
 
-                    if state.level.is_marked(neighbor_axial) {
-                        neighbor_count += 1;
-                    }
-                }
+    run_ui(state, input_state);
+
+    let mut change = Change::None;
+
+    for action in input_state.action_queue.drain(..) {
+        change = change.merge(apply_action(state, action));
+    }
+
+    if !state.is_playing() && !matches!(change, Change::None) {
+        state.edited = true;
+    }
+
+    if let Some((request, level)) = state.level_worker.poll() {
+        change = change.merge(replace_level(state, request, level));
+        dirty = true;
+    }
+
+    match &change {
+        Change::None => {}
+        Change::Positions(positions) => state.hints.update(&state.level, positions),
+        Change::Level => state.hints.rebuild(&state.level),
+    }
+
+    if !matches!(change, Change::None) {
+        state.selection.retain_existing(&state.level);
+        state.diagnostics = validate(&state.level);
+        state.live_solver.submit(&state.level);
+    }
 
-                cell.update_neighbors(neighbor_count);
+    update_inspected(state);
+
+    dirty |= state.live_solver.poll();
+
+    // The frame that finishes an animation is drawn too.
+    let animating = state.animations.is_active();
+    state.animations.tick(now, state.cursor_hex_position);
+    dirty |= animating || state.animations.is_active();
+
+    dirty |= state.frame_stats.refresh(now) && state.frame_stats.visible;
+    dirty |= state.status.expire(now);
+
+    dirty
+}
+
+/// Puts a level built in the background in place of the current one.
+fn replace_level(state: &mut GameState, request: LevelRequest, level: Level) -> Change {
+    state.level = level;
+    state.selection.clear();
+    state.edited = false;
+
+    match request {
+        LevelRequest::Generate { seed, .. } => {
+            if state.is_playing() {
+                state.mode = Mode::Edit;
+                state.animations.clear();
             }
-            _ => {}
+
+            state
+                .status
+                .show(format!("Generated a level with seed {}.", seed));
+        }
+        LevelRequest::Daily(date) => {
+            state.start_playing(Some(date));
+            state
+                .status
+                .show(format!("Playing the daily level for {}.", date));
         }
     }
+
+    Change::Level
+}
+
+/// Runs the interface for this update, keeping the input it handles from the level and
+/// queueing the actions picked in it.
+fn run_ui(state: &mut GameState, input_state: &mut InputState) {
+    let queue = &input_state.action_queue;
+    let input = UiInput {
+        pointer: input_state.absolute_mouse_position,
+        pressed: queue
+            .iter()
+            .any(|action| matches!(action, InputAction::PointerDown(PointerButton::Primary, _))),
+        released: queue
+            .iter()
+            .any(|action| matches!(action, InputAction::PointerUp(PointerButton::Primary))),
+        typed: input_state.typed.drain(..).collect(),
+    };
+
+    // The interface is taken out of the state while it runs, so widgets can edit the state.
+    let mut ui = std::mem::replace(&mut state.ui, Ui::new());
+    let mut actions = Vec::new();
+
+    ui.begin(input);
+
+    if state.menu_open {
+        menu::show(&mut ui, state, &mut actions);
+    }
+
+    ui.end();
+
+    let captures_pointer = ui.captures_pointer();
+    let captures_keyboard = ui.captures_keyboard() || state.menu_open;
+    // A drag started on the level ends there, even over the interface.
+    let dragging = state.drag.is_some();
+
+    input_state.action_queue.retain(|action| match action {
+        InputAction::PointerDown(..) => !captures_pointer,
+        InputAction::PointerUp(_) => !captures_pointer || dragging,
+        InputAction::ToggleMenu => !ui.captures_keyboard(),
+        _ => !captures_keyboard,
+    });
+
+    input_state.action_queue.extend(actions);
+    state.ui = ui;
+}
+
+/// When the game next needs an update without any input, or `None` if it can wait for input.
+pub fn next_update(state: &GameState, now: Instant) -> Option<Instant> {
+    let next = if state.animations.is_active() {
+        Some(now + FRAME_INTERVAL)
+    } else if state.live_solver.is_pending() || state.level_worker.is_pending() {
+        Some(now + SOLVER_POLL_INTERVAL)
+    } else if state.frame_stats.visible {
+        Some(state.frame_stats.next_refresh())
+    } else {
+        None
+    };
+
+    // The status message is taken down when it expires.
+    match (next, state.status.expires_at()) {
+        (Some(next), Some(expires_at)) => Some(next.min(expires_at)),
+        (next, expires_at) => next.or(expires_at),
+    }
 }
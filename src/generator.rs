@@ -0,0 +1,336 @@
+use rand::RngCore;
+use rayon::prelude::*;
+
+use crate::{
+    difficulty::rate_level,
+    hexagon::{axial_neighbors, Axial},
+    level::{CellState, Hex, HintModifier, Level, LineDirection, LineHint},
+    rng::{LevelRng, RngVersion},
+    rules::Arrangement,
+    shapes::Shape,
+    solver::solve,
+};
+
+const AROUND_CHANCE: f64 = 0.15;
+const LINE_CHANCE: f64 = 0.3;
+const MODIFIER_CHANCE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HintKinds {
+    pub neighbors: bool,
+    pub around: bool,
+    pub lines: bool,
+    pub modifiers: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorParams {
+    pub shape: Shape,
+    /// Chance of each cell being marked.
+    pub marked_density: f64,
+    pub hint_kinds: HintKinds,
+    /// Difficulty score to aim for. See `Difficulty::score`.
+    pub target_difficulty: Option<f32>,
+    /// How many levels to generate when aiming for a difficulty.
+    pub candidates: usize,
+    pub rng_version: RngVersion,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            shape: Shape::Hexagon { radius: 4 },
+            marked_density: 0.35,
+            hint_kinds: HintKinds {
+                neighbors: true,
+                around: true,
+                lines: true,
+                modifiers: false,
+            },
+            target_difficulty: None,
+            candidates: 8,
+            rng_version: RngVersion::LATEST,
+        }
+    }
+}
+
+/// Generates a level that can be solved without guessing. The same seed and parameters always
+/// produce the same level, as long as the generator itself doesn't change.
+pub fn generate(seed: u64, params: &GeneratorParams) -> Level {
+    let target = match params.target_difficulty {
+        Some(target) => target,
+        None => return generate_candidate(seed, params),
+    };
+
+    let mut rng = LevelRng::new(params.rng_version, seed);
+    let seeds: Vec<u64> = (0..params.candidates.max(1))
+        .map(|_| rng.next_u64())
+        .collect();
+
+    let candidates: Vec<(f32, Level)> = seeds
+        .into_par_iter()
+        .map(|seed| {
+            let level = generate_candidate(seed, params);
+            let score = rate_level(&level).score;
+            ((score - target).abs(), level)
+        })
+        .collect();
+
+    candidates
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, level)| level)
+        .unwrap()
+}
+
+fn generate_candidate(seed: u64, params: &GeneratorParams) -> Level {
+    let mut rng = LevelRng::new(params.rng_version, seed);
+    let kinds = params.hint_kinds;
+
+    let mut level = Level::new();
+
+    for cell in params.shape.cells() {
+        let hex = if rng.chance(params.marked_density) {
+            Hex::Marked {
+                show_around: kinds.around && rng.chance(AROUND_CHANCE),
+            }
+        } else {
+            Hex::Empty {
+                show_neighbor_count: kinds.neighbors,
+                modifier: HintModifier::Plain,
+            }
+        };
+
+        level.cells.insert(cell, CellState::new(hex));
+    }
+
+    let mut positions: Vec<Axial> = level.cells.keys().copied().collect();
+    positions.sort_by_key(|position| (position.x, position.y));
+
+    if kinds.modifiers {
+        for position in &positions {
+            let ring: Vec<bool> = axial_neighbors(*position)
+                .map(|neighbor| level.is_marked(neighbor))
+                .collect();
+
+            let modifier = modifier_for(&ring, true);
+
+            if let Hex::Empty {
+                show_neighbor_count: true,
+                modifier: current,
+            } = &mut level.cells.get_mut(position).unwrap().hex
+            {
+                if rng.chance(MODIFIER_CHANCE) {
+                    *current = modifier;
+                }
+            }
+        }
+    }
+
+    if kinds.lines {
+        for direction in LineDirection::ALL.iter().copied() {
+            for position in &positions {
+                let origin = position - direction.step();
+
+                if level.cells.contains_key(&origin)
+                    || level.line_hints.contains_key(&origin)
+                    || !rng.chance(LINE_CHANCE)
+                {
+                    continue;
+                }
+
+                let line: Vec<bool> = level
+                    .line_cells(origin, direction)
+                    .into_iter()
+                    .map(|cell| level.is_marked(cell))
+                    .collect();
+
+                let modifier = if kinds.modifiers && rng.chance(MODIFIER_CHANCE) {
+                    modifier_for(&line, false)
+                } else {
+                    HintModifier::Plain
+                };
+
+                level.line_hints.insert(
+                    origin,
+                    LineHint {
+                        direction,
+                        modifier,
+                    },
+                );
+            }
+        }
+    }
+
+    reveal_until_solvable(&mut level, &mut rng);
+
+    level
+}
+
+/// The modifier describing a ring or line of cells, if it has enough marked cells for one.
+fn modifier_for(marked: &[bool], cyclic: bool) -> HintModifier {
+    if marked.iter().filter(|&&marked| marked).count() < 2 {
+        return HintModifier::Plain;
+    }
+
+    let contiguous = Arrangement {
+        modifier: HintModifier::Contiguous,
+        slots: (0..marked.len()).map(Some).collect(),
+        cyclic,
+    };
+
+    if contiguous.is_satisfied(|index| marked[index]) {
+        HintModifier::Contiguous
+    } else {
+        HintModifier::Separated
+    }
+}
+
+/// Reveals random empty cells the solver gets stuck on until it can finish the level.
+fn reveal_until_solvable(level: &mut Level, rng: &mut LevelRng) {
+    loop {
+        let trace = solve(level);
+
+        let mut stuck: Vec<Axial> = trace
+            .stuck
+            .into_iter()
+            .filter(|cell| !level.is_marked(*cell))
+            .collect();
+        stuck.sort_by_key(|position| (position.x, position.y));
+
+        if stuck.is_empty() {
+            break;
+        }
+
+        let cell = stuck[rng.below(stuck.len())];
+        level.cells.get_mut(&cell).unwrap().start_revealed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{write_level, Format};
+
+    const SHAPES: [Shape; 4] = [
+        Shape::Hexagon { radius: 3 },
+        Shape::Triangle { size: 6 },
+        Shape::Rhombus {
+            width: 5,
+            height: 4,
+        },
+        Shape::Rectangle {
+            width: 6,
+            height: 4,
+        },
+    ];
+
+    fn params(shape: Shape, hint_kinds: HintKinds) -> GeneratorParams {
+        GeneratorParams {
+            shape,
+            hint_kinds,
+            ..GeneratorParams::default()
+        }
+    }
+
+    fn text(level: &Level) -> String {
+        write_level(level, Format::Native).unwrap()
+    }
+
+    fn all_kinds() -> HintKinds {
+        HintKinds {
+            neighbors: true,
+            around: true,
+            lines: true,
+            modifiers: true,
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_level() {
+        for shape in SHAPES.iter().cloned() {
+            let params = params(shape, all_kinds());
+
+            assert_eq!(text(&generate(7, &params)), text(&generate(7, &params)));
+        }
+
+        let params = GeneratorParams {
+            target_difficulty: Some(30.0),
+            candidates: 3,
+            ..params(SHAPES[0].clone(), all_kinds())
+        };
+        assert_eq!(text(&generate(7, &params)), text(&generate(7, &params)));
+    }
+
+    #[test]
+    fn different_seeds_give_different_levels() {
+        let params = params(SHAPES[0].clone(), all_kinds());
+        let levels: Vec<String> = (0..4).map(|seed| text(&generate(seed, &params))).collect();
+
+        for (i, level) in levels.iter().enumerate() {
+            assert!(!levels[i + 1..].contains(level), "seed {} repeats", i);
+        }
+    }
+
+    #[test]
+    fn solver_finishes_every_level() {
+        for shape in SHAPES.iter().cloned() {
+            let params = params(shape, all_kinds());
+
+            for seed in 0..8 {
+                let level = generate(seed, &params);
+                let trace = solve(&level);
+
+                assert!(trace.is_solved(), "{} seed {}", params.shape.name(), seed);
+
+                for deduction in trace.steps.iter().flat_map(|step| &step.deductions) {
+                    assert_eq!(deduction.marked, level.is_marked(deduction.cell));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hint_kinds_are_honored() {
+        let none = HintKinds {
+            neighbors: false,
+            around: false,
+            lines: false,
+            modifiers: false,
+        };
+
+        for seed in 0..8 {
+            let level = generate(seed, &params(SHAPES[0].clone(), none));
+
+            assert!(level.line_hints.is_empty());
+            for cell in level.cells.values() {
+                match cell.hex {
+                    Hex::Empty {
+                        show_neighbor_count,
+                        modifier,
+                    } => {
+                        assert!(!show_neighbor_count);
+                        assert_eq!(modifier, HintModifier::Plain);
+                    }
+                    Hex::Marked { show_around } => assert!(!show_around),
+                }
+            }
+        }
+
+        // Over a few seeds every kind turns up when allowed.
+        let levels: Vec<Level> = (0..8)
+            .map(|seed| generate(seed, &params(SHAPES[0].clone(), all_kinds())))
+            .collect();
+        let cells = || levels.iter().flat_map(|level| level.cells.values());
+
+        assert!(levels.iter().any(|level| !level.line_hints.is_empty()));
+        assert!(cells().any(|cell| matches!(cell.hex, Hex::Marked { show_around: true })));
+        assert!(cells().any(|cell| matches!(
+            cell.hex,
+            Hex::Empty {
+                modifier: HintModifier::Contiguous | HintModifier::Separated,
+                ..
+            }
+        )));
+    }
+}
@@ -1,76 +1,13 @@
-use cgmath::{num_traits::Signed, EuclideanSpace, MetricSpace, Point2, Vector2, Vector3, Zero};
-use luminance::{context::GraphicsContext, tess::Interleaved, vertex::Vertex};
-use luminance_front::{tess::Tess, Backend};
-
-use crate::render::{
-    BasicVertex, BasicVertexPosition, HexVertex, HexVertexEdginess, HexVertexPosition,
-    SmallVertexIndex,
-};
+use cgmath::{num_traits::Signed, EuclideanSpace, MetricSpace, Point2, Vector2, Vector3};
 
 pub type Axial = Vector2<i32>;
 pub type AxialF = Vector2<f32>;
 pub type Cube = Vector3<i32>;
 pub type CubeF = Vector3<f32>;
 
-fn create_mesh<C, V: Vertex>(
-    context: &mut C,
-    create_vertex: impl Fn(Vector2<f32>, bool) -> V,
-) -> Tess<V, SmallVertexIndex, (), Interleaved>
-where
-    V: Vertex,
-    C: GraphicsContext<Backend = Backend>,
-{
-    let mut verts = Vec::with_capacity(7);
-
-    verts.push(create_vertex(Vector2::zero(), true));
-
-    for i in 0..6 {
-        let outer_position = hex_corner(Vector2::new(0.0, 0.0), 0.95, i);
-        let vert = create_vertex(outer_position.into(), false);
-        verts.push(vert);
-    }
-
-    #[rustfmt::skip]
-        let indices: &[SmallVertexIndex] = &[
-            0, 1, 2, 3, 4, 5, 6, 1
-        ];
-
-    context
-        .new_tess()
-        .set_vertices(verts)
-        .set_indices(indices)
-        .set_mode(luminance::tess::Mode::TriangleFan)
-        .build()
-        .unwrap()
-}
-
-pub fn create_hexagon_mesh_border<C>(
-    context: &mut C,
-) -> Tess<HexVertex, SmallVertexIndex, (), Interleaved>
-where
-    C: GraphicsContext<Backend = Backend>,
-{
-    create_mesh(context, |position, is_center| HexVertex {
-        position: HexVertexPosition::new(position.into()),
-        edginess: HexVertexEdginess::new(if is_center { 0.0 } else { 1.0 }),
-    })
-}
-
-#[allow(dead_code)]
-pub fn create_hexagon_mesh<C>(
-    context: &mut C,
-) -> Tess<BasicVertex, SmallVertexIndex, (), Interleaved>
-where
-    C: GraphicsContext<Backend = Backend>,
-{
-    create_mesh(context, |position, _is_center| BasicVertex {
-        position: BasicVertexPosition::new(position.into()),
-    })
-}
-
 // https://www.redblobgames.com/grids/hexagons/
 
-fn hex_corner(center: Vector2<f32>, size: f32, i: usize) -> Vector2<f32> {
+pub fn hex_corner(center: Vector2<f32>, size: f32, i: usize) -> Vector2<f32> {
     assert!(i <= 5, "i must be between 0 and 6");
 
     let angle_deg = 60.0 * i as f32;
@@ -147,7 +84,6 @@ pub const CUBE_DIRECTIONS: [Cube; 6] = [
     Cube::new(0, -1, 1),
 ];
 
-#[allow(dead_code)]
 pub const AXIAL_DIRECTION: [Axial; 6] = [
     Axial::new(1, 0),
     Axial::new(1, -1),
@@ -157,6 +93,21 @@ pub const AXIAL_DIRECTION: [Axial; 6] = [
     Axial::new(0, 1),
 ];
 
+pub fn hex_distance(a: Axial, b: Axial) -> i32 {
+    let difference = axial_to_cube(a - b);
+    difference
+        .x
+        .abs()
+        .max(difference.y.abs())
+        .max(difference.z.abs())
+}
+
+pub fn axial_neighbors(axial: Axial) -> impl Iterator<Item = Axial> {
+    AXIAL_DIRECTION
+        .iter()
+        .map(move |direction| axial + direction)
+}
+
 fn cube_neighbor(cube: Cube, direction: usize) -> Cube {
     cube + CUBE_DIRECTIONS[direction]
 }
@@ -178,6 +129,12 @@ pub fn spiral_ring(center: Cube, radius: u32, results: &mut Vec<Cube>) {
     }
 }
 
+pub fn axial_spiral(center: Axial, radius: u32) -> Vec<Axial> {
+    let mut cubes = Vec::new();
+    spiral_ring(axial_to_cube(center), radius, &mut cubes);
+    cubes.into_iter().map(cube_to_axial).collect()
+}
+
 pub fn nearest_edge_hex(pixel_pos: Vector2<f32>, scale: f32) -> Vector2<f32> {
     let axial_f = pixel_to_flat_hex_f(pixel_pos, scale);
     let axial = hex_round(axial_f);
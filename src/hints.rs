@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use crate::{
+    hexagon::{axial_spiral, Axial},
+    level::{Hex, Level},
+};
+
+/// Hint counts shown on the board, kept up to date as the level is edited instead of being
+/// recounted for the whole level after every change.
+///
+/// Counts are kept for every cell and line hint, whether or not they are shown, so hiding a hint
+/// doesn't need an update.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HintCache {
+    /// Marked neighbors of empty cells and marked cells within two steps of marked cells.
+    cells: HashMap<Axial, usize>,
+    /// Marked cells on each line hint's line, keyed by its origin.
+    lines: HashMap<Axial, usize>,
+}
+
+impl HintCache {
+    pub fn new(level: &Level) -> HintCache {
+        let mut cache = HintCache::default();
+        cache.rebuild(level);
+        cache
+    }
+
+    pub fn cell_count(&self, position: Axial) -> Option<usize> {
+        self.cells.get(&position).copied()
+    }
+
+    pub fn line_count(&self, origin: Axial) -> Option<usize> {
+        self.lines.get(&origin).copied()
+    }
+
+    /// Recounts every hint, for edits that touch most of the level.
+    pub fn rebuild(&mut self, level: &Level) {
+        self.cells = level
+            .cells
+            .keys()
+            .filter_map(|position| Some((*position, count_cell(level, *position)?)))
+            .collect();
+
+        self.lines = level
+            .line_hints
+            .iter()
+            .map(|(origin, hint)| (*origin, level.line_count(*origin, hint.direction)))
+            .collect();
+    }
+
+    /// Recounts the hints that can depend on the cells or line hints at `changed`: counts on
+    /// cells within two steps of them and line hints on lines passing through them.
+    ///
+    /// Debug builds check the result against a full recount, which costs as much as `rebuild`.
+    pub fn update(&mut self, level: &Level, changed: &[Axial]) {
+        for position in changed {
+            let mut nearby = axial_spiral(*position, 2);
+            nearby.push(*position);
+
+            for cell in nearby {
+                match count_cell(level, cell) {
+                    Some(count) => self.cells.insert(cell, count),
+                    None => self.cells.remove(&cell),
+                };
+            }
+
+            match level.line_hints.get(position) {
+                Some(hint) => {
+                    let count = level.line_count(*position, hint.direction);
+                    self.lines.insert(*position, count);
+                }
+                None => {
+                    self.lines.remove(position);
+                }
+            }
+        }
+
+        for (origin, hint) in &level.line_hints {
+            let step = hint.direction.step();
+
+            let crosses = changed
+                .iter()
+                .any(|position| is_on_ray(*position - *origin, step));
+
+            if crosses {
+                let count = level.line_count(*origin, hint.direction);
+                self.lines.insert(*origin, count);
+            }
+        }
+
+        if cfg!(debug_assertions) {
+            self.assert_matches_full_recount(level);
+        }
+    }
+
+    /// Panics if any cached count differs from counting the level from scratch.
+    pub fn assert_matches_full_recount(&self, level: &Level) {
+        let full = HintCache::new(level);
+
+        for (position, count) in &full.cells {
+            assert_eq!(
+                self.cells.get(position),
+                Some(count),
+                "stale count on the cell at {:?}",
+                position
+            );
+        }
+
+        for (origin, count) in &full.lines {
+            assert_eq!(
+                self.lines.get(origin),
+                Some(count),
+                "stale count on the line hint at {:?}",
+                origin
+            );
+        }
+
+        assert_eq!(
+            self.cells.len(),
+            full.cells.len(),
+            "counts left on removed cells"
+        );
+        assert_eq!(
+            self.lines.len(),
+            full.lines.len(),
+            "counts left on removed line hints"
+        );
+    }
+}
+
+/// The count a cell shows when its hint is enabled, or `None` if there's no cell.
+fn count_cell(level: &Level, position: Axial) -> Option<usize> {
+    match level.cells.get(&position)?.hex {
+        Hex::Empty { .. } => Some(level.neighbor_count(position)),
+        Hex::Marked { .. } => Some(level.around_count(position)),
+    }
+}
+
+/// Whether `offset` is a positive multiple of `step`.
+fn is_on_ray(offset: Axial, step: Axial) -> bool {
+    let distance = if step.x != 0 {
+        offset.x / step.x
+    } else {
+        offset.y / step.y
+    };
+
+    distance > 0 && step * distance == offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{CellState, HintModifier, LineDirection, LineHint},
+        test_levels::level,
+    };
+
+    #[test]
+    fn updates_match_a_full_recount() {
+        let mut level = level(
+            "cell 0 0 empty number
+             cell 1 0 marked
+             cell 0 1 empty
+             cell 2 0 marked
+             cell 0 3 marked
+             line 0 -1 down",
+        );
+        let mut cache = HintCache::new(&level);
+
+        let edits: [&dyn Fn(&mut Level) -> Axial; 5] = [
+            // Marking a cell changes its neighbors' counts, the around counts of marked cells
+            // within two steps and the line through it.
+            &|level| {
+                level.cells.get_mut(&Axial::new(0, 1)).unwrap().hex =
+                    Hex::Marked { show_around: false };
+                Axial::new(0, 1)
+            },
+            &|level| {
+                level.cells.remove(&Axial::new(1, 0));
+                Axial::new(1, 0)
+            },
+            &|level| {
+                level.cells.insert(
+                    Axial::new(0, 2),
+                    CellState::new(Hex::Marked { show_around: true }),
+                );
+                Axial::new(0, 2)
+            },
+            &|level| {
+                level.line_hints.insert(
+                    Axial::new(-1, 1),
+                    LineHint {
+                        direction: LineDirection::DownRight,
+                        modifier: HintModifier::Plain,
+                    },
+                );
+                Axial::new(-1, 1)
+            },
+            &|level| {
+                level.line_hints.remove(&Axial::new(0, -1));
+                Axial::new(0, -1)
+            },
+        ];
+
+        for (index, edit) in edits.iter().enumerate() {
+            let changed = edit(&mut level);
+            cache.update(&level, &[changed]);
+
+            assert_eq!(cache, HintCache::new(&level), "after edit {}", index);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "stale count")]
+    fn recount_catches_a_stale_cache() {
+        let mut level = level(
+            "cell 0 0 empty number
+             cell 1 0 empty",
+        );
+        let cache = HintCache::new(&level);
+
+        level.cells.get_mut(&Axial::new(1, 0)).unwrap().hex = Hex::Marked { show_around: false };
+        cache.assert_matches_full_recount(&level);
+    }
+
+    #[test]
+    fn rays_only_run_forward() {
+        let step = Axial::new(0, 1);
+
+        assert!(is_on_ray(Axial::new(0, 3), step));
+        assert!(!is_on_ray(Axial::new(0, 0), step));
+        assert!(!is_on_ray(Axial::new(0, -2), step));
+        assert!(!is_on_ray(Axial::new(1, 2), step));
+    }
+}
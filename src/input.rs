@@ -1,23 +1,93 @@
+use std::path::PathBuf;
+
 use cgmath::{Vector2, Zero};
 
-#[derive(Debug)]
+use opencells_rs::selection::SelectMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HexKind {
     Empty,
     Marked,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerButton {
+    Primary,
+    Secondary,
+}
+
 #[derive(Debug)]
 pub enum InputAction {
     ClearHex,
     PlaceHex(HexKind),
     RingDebug,
     ToggleRevealed,
+    CheckUniqueness,
+    RateDifficulty,
+    GenerateLevel,
+    PruneLevel,
+    StampShape,
+    NextStampShape,
+    /// Reads a mask from a file and stamps with it.
+    LoadMask(PathBuf),
+    /// Starts a selection or a move while editing, or guesses the kind of the cell under the
+    /// cursor while playing.
+    PointerDown(PointerButton, SelectMode),
+    PointerUp(PointerButton),
+    /// Selects the cells connected to the one under the cursor.
+    FloodSelect(SelectMode),
+    SelectAll,
+    ClearSelection,
+    DeleteSelection,
+    Copy,
+    Paste,
+    TogglePlay,
+    /// Moves between the rows of the inspector.
+    PreviousProperty,
+    NextProperty,
+    /// Steps the property on the inspector's current row.
+    ChangeProperty {
+        forward: bool,
+    },
+    /// Cycles through the ways edits are mirrored or rotated.
+    NextSymmetry,
+    SetSymmetryCenter,
+    StartDaily,
+    /// Cycles through the built-in themes.
+    NextTheme,
+    /// Turns cell animations off or back on.
+    ToggleReduceMotion,
+    /// Shows or hides how often frames are drawn.
+    ToggleFrameStats,
+    /// Opens or closes the menu.
+    ToggleMenu,
 }
 
 pub struct InputState {
     pub action_queue: Vec<InputAction>,
+    /// Positions and sizes are in logical pixels, which the window's scale factor turns into
+    /// physical ones.
     pub absolute_mouse_position: Vector2<f32>,
     pub mouse_position: Vector2<f32>,
+    pub viewport_size: Vector2<f32>,
+    pub scale_factor: f64,
+    /// Characters typed since the last update, for text inputs.
+    pub typed: String,
+    pub shift: bool,
+    pub ctrl: bool,
+}
+
+impl InputState {
+    /// Shift adds to the selection and control removes from it.
+    pub fn select_mode(&self) -> SelectMode {
+        if self.shift {
+            SelectMode::Add
+        } else if self.ctrl {
+            SelectMode::Remove
+        } else {
+            SelectMode::Replace
+        }
+    }
 }
 
 impl Default for InputState {
@@ -26,6 +96,11 @@ impl Default for InputState {
             action_queue: Vec::new(),
             absolute_mouse_position: Vector2::zero(),
             mouse_position: Vector2::zero(),
+            viewport_size: Vector2::zero(),
+            scale_factor: 1.0,
+            typed: String::new(),
+            shift: false,
+            ctrl: false,
         }
     }
 }
@@ -0,0 +1,206 @@
+//! The panel along the right edge that lists the properties of the inspected cell while
+//! editing, and how stepping each property changes the level.
+
+use cgmath::Vector2;
+
+use crate::{
+    hexagon::Axial,
+    level::{CellState, Hex, HintModifier, Level},
+};
+
+/// Width of the panel, which sits along the right edge of the window.
+pub const PANEL_WIDTH: f32 = 320.0;
+pub const ROW_HEIGHT: f32 = 28.0;
+/// Leaves room for the mistake counter above the panel.
+const PANEL_TOP: f32 = 160.0;
+const PANEL_MARGIN: f32 = 16.0;
+
+/// An editable property of a cell, shown as a row in the inspector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Kind,
+    /// The neighbor count of an empty cell or the count around a marked cell.
+    ShowCount,
+    Modifier,
+    StartRevealed,
+    Q,
+    R,
+}
+
+/// The properties a cell has, in the order they are listed.
+pub fn properties(cell: &CellState) -> &'static [Property] {
+    match cell.hex {
+        Hex::Empty { .. } => &[
+            Property::Kind,
+            Property::ShowCount,
+            Property::Modifier,
+            Property::StartRevealed,
+            Property::Q,
+            Property::R,
+        ],
+        Hex::Marked { .. } => &[
+            Property::Kind,
+            Property::ShowCount,
+            Property::StartRevealed,
+            Property::Q,
+            Property::R,
+        ],
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+pub fn describe(cell: &CellState, position: Axial, property: Property) -> String {
+    match (property, cell.hex) {
+        (Property::Kind, Hex::Empty { .. }) => "Kind: empty".to_string(),
+        (Property::Kind, Hex::Marked { .. }) => "Kind: marked".to_string(),
+        (
+            Property::ShowCount,
+            Hex::Empty {
+                show_neighbor_count,
+                ..
+            },
+        ) => format!("Neighbor count: {}", yes_no(show_neighbor_count)),
+        (Property::ShowCount, Hex::Marked { show_around }) => {
+            format!("Count around: {}", yes_no(show_around))
+        }
+        (Property::Modifier, Hex::Empty { modifier, .. }) => {
+            let name = match modifier {
+                HintModifier::Plain => "plain",
+                HintModifier::Contiguous => "{n} contiguous",
+                HintModifier::Separated => "-n- separated",
+            };
+
+            format!("Modifier: {}", name)
+        }
+        (Property::Modifier, Hex::Marked { .. }) => "Modifier: none".to_string(),
+        (Property::StartRevealed, _) => {
+            format!("Revealed at start: {}", yes_no(cell.start_revealed))
+        }
+        (Property::Q, _) => format!("q: {}", position.x),
+        (Property::R, _) => format!("r: {}", position.y),
+    }
+}
+
+/// Steps a property of the cell at `position` forwards or backwards. Returns where the cell is
+/// afterwards, or `None` if nothing changed.
+pub fn change(
+    level: &mut Level,
+    position: Axial,
+    property: Property,
+    forward: bool,
+) -> Option<Axial> {
+    let step = if forward { 1 } else { -1 };
+
+    let target = match property {
+        Property::Q => position + Axial::new(step, 0),
+        Property::R => position + Axial::new(0, step),
+        _ => position,
+    };
+
+    if target != position {
+        if level.cells.contains_key(&target) || level.line_hints.contains_key(&target) {
+            return None;
+        }
+
+        let cell = level.cells.remove(&position)?;
+        level.cells.insert(target, cell);
+        return Some(target);
+    }
+
+    let cell = level.cells.get_mut(&position)?;
+
+    cell.hex = match (property, cell.hex) {
+        (Property::Kind, Hex::Empty { .. }) => Hex::Marked { show_around: false },
+        (Property::Kind, Hex::Marked { .. }) => Hex::Empty {
+            show_neighbor_count: true,
+            modifier: HintModifier::Plain,
+        },
+        (
+            Property::ShowCount,
+            Hex::Empty {
+                show_neighbor_count,
+                modifier,
+            },
+        ) => Hex::Empty {
+            show_neighbor_count: !show_neighbor_count,
+            modifier,
+        },
+        (Property::ShowCount, Hex::Marked { show_around }) => Hex::Marked {
+            show_around: !show_around,
+        },
+        (
+            Property::Modifier,
+            Hex::Empty {
+                show_neighbor_count,
+                modifier,
+            },
+        ) => {
+            let count = HintModifier::ALL.len();
+            let index = HintModifier::ALL
+                .iter()
+                .position(|other| *other == modifier)
+                .unwrap_or(0);
+            let index = if forward {
+                (index + 1) % count
+            } else {
+                (index + count - 1) % count
+            };
+
+            Hex::Empty {
+                show_neighbor_count,
+                modifier: HintModifier::ALL[index],
+            }
+        }
+        (Property::StartRevealed, hex) => {
+            cell.start_revealed = !cell.start_revealed;
+            hex
+        }
+        _ => return None,
+    };
+
+    Some(position)
+}
+
+/// Top left corner of a row, counting the heading above the properties as row 0.
+pub fn row_position(viewport_width: f32, row: usize) -> Vector2<f32> {
+    Vector2::new(
+        viewport_width - PANEL_WIDTH - PANEL_MARGIN,
+        PANEL_TOP + row as f32 * ROW_HEIGHT,
+    )
+}
+
+/// The property row under a point on screen, as an index into `properties`.
+pub fn row_at(viewport_width: f32, point: Vector2<f32>, rows: usize) -> Option<usize> {
+    let top_left = row_position(viewport_width, 1);
+    let relative = point - top_left;
+
+    if relative.x < 0.0 || relative.x > PANEL_WIDTH || relative.y < 0.0 {
+        return None;
+    }
+
+    let row = (relative.y / ROW_HEIGHT) as usize;
+
+    if row < rows {
+        Some(row)
+    } else {
+        None
+    }
+}
+
+/// Whether a point on screen is over the panel.
+pub fn contains(viewport_width: f32, point: Vector2<f32>, rows: usize) -> bool {
+    let top_left = row_position(viewport_width, 0);
+    let relative = point - top_left;
+
+    relative.x >= 0.0
+        && relative.x <= PANEL_WIDTH
+        && relative.y >= 0.0
+        && relative.y <= (rows + 1) as f32 * ROW_HEIGHT
+}
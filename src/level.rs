@@ -1,33 +1,63 @@
-use std::{cell::Cell, collections::HashMap};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+};
 
 type MemoryCell<T> = Cell<T>;
 
-use cgmath::{Vector2, Vector3};
+use cgmath::Vector2;
 
-use crate::hexagon::Axial;
+use crate::hexagon::{axial_neighbors, axial_spiral, hex_distance, Axial};
 
-#[derive(Debug)]
-pub enum Hex {
-    Empty { show_neighbor_count: bool },
-    Marked { show_around: bool },
+/// Tells whether the marked cells counted by a hint form a single group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintModifier {
+    Plain,
+    /// Shown as `{n}`: the marked cells are next to each other.
+    Contiguous,
+    /// Shown as `-n-`: the marked cells are split into at least two groups.
+    Separated,
 }
 
-impl Hex {
-    pub fn get_color(&self, revealed: bool) -> Vector3<f32> {
+impl HintModifier {
+    pub const ALL: [HintModifier; 3] = [
+        HintModifier::Plain,
+        HintModifier::Contiguous,
+        HintModifier::Separated,
+    ];
+
+    /// Formats a count the way it's shown in game.
+    pub fn decorate(self, count: usize) -> String {
         match self {
-            _ if !revealed => Vector3::new(0.960, 0.505, 0.058),
-            Hex::Empty { .. } => Vector3::new(0.368, 0.368, 0.368),
-            Hex::Marked { .. } => Vector3::new(0.058, 0.533, 0.960),
+            HintModifier::Plain => count.to_string(),
+            HintModifier::Contiguous => format!("{{{}}}", count),
+            HintModifier::Separated => format!("-{}-", count),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum Hex {
+    Empty {
+        show_neighbor_count: bool,
+        modifier: HintModifier,
+    },
+    Marked {
+        show_around: bool,
+    },
+}
+
+impl Hex {
+    pub fn is_marked(&self) -> bool {
+        matches!(self, Hex::Marked { .. })
+    }
+}
+
 #[derive(Debug)]
 pub struct CellState {
     pub hex: Hex,
     pub start_revealed: bool,
     revealed: MemoryCell<bool>,
-    marked_neighbors: MemoryCell<usize>,
 }
 
 impl CellState {
@@ -36,60 +66,163 @@ impl CellState {
             hex,
             start_revealed: false,
             revealed: MemoryCell::new(false),
-            marked_neighbors: MemoryCell::new(0),
         }
     }
 
-    pub fn neighbors(&self) -> usize {
-        self.marked_neighbors.get()
+    pub fn is_revealed(&self) -> bool {
+        self.revealed.get()
     }
 
-    pub fn neighbors_str(&self) -> &'static str {
-        let neighbors = self.neighbors();
-
-        match neighbors {
-            0 => "0",
-            1 => "1",
-            2 => "2",
-            3 => "3",
-            4 => "4",
-            5 => "5",
-            6 => "6",
-            _ => unreachable!("A cell can have up to 6 neighbors."),
-        }
+    pub fn reveal(&self) {
+        self.revealed.set(true)
     }
 
-    pub fn is_revealed(&self) -> bool {
-        self.revealed.get()
+    /// Puts the cell back to how it is when the level starts.
+    pub fn reset_revealed(&self) {
+        self.revealed.set(self.start_revealed)
     }
+}
 
-    pub fn update_neighbors(&self, neighbors: usize) {
-        self.marked_neighbors.set(neighbors)
-    }
+/// Direction a line hint points in, towards the cells it counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineDirection {
+    Down,
+    DownRight,
+    DownLeft,
+}
 
-    pub fn reveal(&self) {
-        self.revealed.set(true)
+impl LineDirection {
+    pub const ALL: [LineDirection; 3] = [
+        LineDirection::Down,
+        LineDirection::DownRight,
+        LineDirection::DownLeft,
+    ];
+
+    pub fn step(self) -> Axial {
+        match self {
+            LineDirection::Down => Axial::new(0, 1),
+            LineDirection::DownRight => Axial::new(1, 0),
+            LineDirection::DownLeft => Axial::new(-1, 1),
+        }
     }
 }
 
+/// A hint outside the cells counting the marked cells on a line.
+#[derive(Debug, Clone, Copy)]
+pub struct LineHint {
+    pub direction: LineDirection,
+    pub modifier: HintModifier,
+}
+
+/// Text shown alongside a level.
+#[derive(Debug, Clone, Default)]
+pub struct LevelInfo {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+}
+
 pub struct Level {
+    pub info: LevelInfo,
     pub cells: HashMap<Vector2<i32>, CellState>,
+    /// Line hints keyed by the position they are displayed at.
+    pub line_hints: HashMap<Axial, LineHint>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level::new()
+    }
 }
 
 impl Level {
     pub fn new() -> Level {
+        let info = LevelInfo::default();
         let cells = HashMap::new();
+        let line_hints = HashMap::new();
+
+        Level {
+            info,
+            cells,
+            line_hints,
+        }
+    }
+
+    /// Cell positions ordered by column and then row, for output that doesn't depend on hashing.
+    pub fn sorted_positions(&self) -> Vec<Axial> {
+        let mut positions: Vec<Axial> = self.cells.keys().copied().collect();
+        positions.sort_by_key(|position| (position.x, position.y));
+        positions
+    }
+
+    pub fn neighbor_count(&self, axial: Axial) -> usize {
+        axial_neighbors(axial)
+            .filter(|neighbor| self.is_marked(*neighbor))
+            .count()
+    }
+
+    /// Marked cells within two steps, as counted by a marked cell's hint.
+    pub fn around_count(&self, axial: Axial) -> usize {
+        axial_spiral(axial, 2)
+            .into_iter()
+            .filter(|cell| self.is_marked(*cell))
+            .count()
+    }
+
+    /// Cells on the line starting next to `origin`, in order.
+    pub fn line_cells(&self, origin: Axial, direction: LineDirection) -> Vec<Axial> {
+        let step = direction.step();
+
+        let furthest = self
+            .cells
+            .keys()
+            .map(|cell| hex_distance(origin, *cell))
+            .max()
+            .unwrap_or(0);
+
+        (1..=furthest)
+            .map(|distance| origin + step * distance)
+            .filter(|position| self.cells.contains_key(position))
+            .collect()
+    }
+
+    pub fn line_count(&self, origin: Axial, direction: LineDirection) -> usize {
+        self.line_cells(origin, direction)
+            .into_iter()
+            .filter(|cell| self.is_marked(*cell))
+            .count()
+    }
+
+    /// Cells reachable from `start` through neighbors, in sorted order. Empty if there's no cell
+    /// at `start`.
+    pub fn connected_cells(&self, start: Axial) -> Vec<Axial> {
+        if !self.cells.contains_key(&start) {
+            return Vec::new();
+        }
+
+        let mut visited = HashSet::new();
+        visited.insert(start);
+
+        let mut cells = vec![start];
+        let mut frontier = vec![start];
+
+        while let Some(position) = frontier.pop() {
+            for neighbor in axial_neighbors(position) {
+                if self.cells.contains_key(&neighbor) && visited.insert(neighbor) {
+                    cells.push(neighbor);
+                    frontier.push(neighbor);
+                }
+            }
+        }
 
-        Level { cells }
+        cells.sort_by_key(|position| (position.x, position.y));
+        cells
     }
 
     pub fn is_marked(&self, axial: Axial) -> bool {
         self.cells
             .get(&axial)
-            .map(|cell| match &cell.hex {
-                Hex::Marked { .. } => true,
-                _ => false,
-            })
+            .map(|cell| cell.hex.is_marked())
             .unwrap_or(false)
     }
 }
@@ -0,0 +1,34 @@
+//! Level model, rules, solver and tooling shared by the editor and the `opencells` command line
+//! tool.
+
+pub mod animation;
+pub mod batch;
+pub mod daily;
+pub mod difficulty;
+pub mod formats;
+pub mod generator;
+pub mod hexagon;
+pub mod hints;
+pub mod inspector;
+pub mod level;
+pub mod live;
+pub mod play;
+pub mod png;
+pub mod prune;
+pub mod raster;
+pub mod rng;
+pub mod rules;
+pub mod scene;
+pub mod search;
+pub mod selection;
+pub mod shapes;
+pub mod solver;
+pub mod svg;
+pub mod symmetry;
+#[cfg(test)]
+mod test_levels;
+pub mod theme;
+pub mod uniqueness;
+pub mod validate;
+pub mod view;
+pub mod worker;
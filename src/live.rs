@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    hexagon::Axial,
+    level::Level,
+    rules::Puzzle,
+    solver::{solve_puzzle, Trace},
+    uniqueness::{check_puzzle_uniqueness_until, Uniqueness},
+};
+
+/// What the solver found out about one version of a level.
+#[derive(Debug)]
+pub struct LiveAnalysis {
+    /// The version of the level, as returned by `LiveSolver::submit`.
+    pub generation: u64,
+    /// The step each hidden cell is deduced in, starting from 0 for cells that can be deduced
+    /// right away.
+    pub steps: HashMap<Axial, usize>,
+    /// Cells the solver couldn't deduce.
+    pub stuck: Vec<Axial>,
+    pub uniqueness: Uniqueness,
+}
+
+impl LiveAnalysis {
+    fn new(generation: u64, trace: Trace, uniqueness: Uniqueness) -> LiveAnalysis {
+        let steps = trace
+            .steps
+            .iter()
+            .enumerate()
+            .flat_map(|(index, step)| {
+                step.deductions
+                    .iter()
+                    .map(move |deduction| (deduction.cell, index))
+            })
+            .collect();
+
+        LiveAnalysis {
+            generation,
+            steps,
+            stuck: trace.stuck,
+            uniqueness,
+        }
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        self.stuck.is_empty()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        matches!(self.uniqueness, Uniqueness::Unique)
+    }
+}
+
+/// Analyzes levels on a background thread as they are edited. Only the newest submitted version
+/// is analyzed once the thread is free, and an analysis is abandoned as soon as a newer version
+/// is submitted, so results can skip versions but never fall far behind.
+pub struct LiveSolver {
+    jobs: Sender<(u64, Puzzle)>,
+    results: Receiver<LiveAnalysis>,
+    generation: u64,
+    /// The last submitted generation, shared with the thread.
+    submitted: Arc<AtomicU64>,
+    latest: Option<LiveAnalysis>,
+}
+
+impl LiveSolver {
+    pub fn new() -> LiveSolver {
+        let (jobs, job_receiver) = channel::<(u64, Puzzle)>();
+        let (result_sender, results) = channel();
+        let submitted = Arc::new(AtomicU64::new(0));
+        let newest = Arc::clone(&submitted);
+
+        thread::spawn(move || {
+            while let Ok(mut job) = job_receiver.recv() {
+                while let Ok(newer) = job_receiver.try_recv() {
+                    job = newer;
+                }
+
+                let (generation, puzzle) = job;
+                let superseded = || newest.load(Ordering::Relaxed) != generation;
+
+                let (trace, uniqueness) = rayon::join(
+                    || solve_puzzle(&puzzle),
+                    || check_puzzle_uniqueness_until(&puzzle, &superseded),
+                );
+
+                // The newer job is already waiting.
+                let uniqueness = match uniqueness {
+                    Some(uniqueness) => uniqueness,
+                    None => continue,
+                };
+
+                let analysis = LiveAnalysis::new(generation, trace, uniqueness);
+
+                if result_sender.send(analysis).is_err() {
+                    break;
+                }
+            }
+        });
+
+        LiveSolver {
+            jobs,
+            results,
+            generation: 0,
+            submitted,
+            latest: None,
+        }
+    }
+
+    /// Queues a level for analysis, returning its generation.
+    pub fn submit(&mut self, level: &Level) -> u64 {
+        self.generation += 1;
+        self.submitted.store(self.generation, Ordering::Relaxed);
+
+        // Levels keep play state in `Cell`s, so the thread gets the flattened puzzle instead.
+        let _ = self.jobs.send((self.generation, Puzzle::from_level(level)));
+
+        self.generation
+    }
+
+    /// Picks up finished analyses, returning whether a newer one arrived.
+    pub fn poll(&mut self) -> bool {
+        let mut updated = false;
+
+        while let Ok(analysis) = self.results.try_recv() {
+            self.latest = Some(analysis);
+            updated = true;
+        }
+
+        updated
+    }
+
+    /// The newest finished analysis, which may be for an older version of the level.
+    pub fn latest(&self) -> Option<&LiveAnalysis> {
+        self.latest.as_ref()
+    }
+
+    /// Whether the newest analysis is for the last submitted level.
+    pub fn is_current(&self) -> bool {
+        self.latest
+            .as_ref()
+            .map(|analysis| analysis.generation == self.generation)
+            .unwrap_or(false)
+    }
+
+    /// Whether a submitted level is still being analyzed.
+    pub fn is_pending(&self) -> bool {
+        let finished = self
+            .latest
+            .as_ref()
+            .map_or(0, |analysis| analysis.generation);
+
+        finished != self.generation
+    }
+}
+
+impl Default for LiveSolver {
+    fn default() -> Self {
+        LiveSolver::new()
+    }
+}
@@ -1,23 +1,46 @@
+mod frame;
+mod frame_stats;
 mod game;
-mod hexagon;
 mod input;
-mod level;
+mod menu;
 mod render;
+mod status;
+mod ui;
+
+use std::{env, time::Instant};
 
 use cgmath::Vector2;
 use glutin::{
     self,
-    dpi::LogicalSize,
-    event::{ElementState, Event, StartCause, VirtualKeyCode, WindowEvent},
+    dpi::{LogicalSize, PhysicalSize},
+    event::{ElementState, Event, MouseButton, StartCause, VirtualKeyCode, WindowEvent},
     event_loop::ControlFlow,
     window::WindowBuilder,
 };
-use input::{HexKind, InputAction, InputState};
+use input::{HexKind, InputAction, InputState, PointerButton};
 
-use game::{update, GameState};
+use frame::build_frame;
+use game::{next_update, update, GameState};
 use luminance_glutin::{self, GlutinSurface};
+use opencells_rs::theme::Theme;
 use render::Renderer;
 
+/// Fits the viewport to a new size in physical pixels, keeping the game in logical pixels.
+fn resize(
+    physical_size: PhysicalSize<u32>,
+    scale_factor: f64,
+    input_state: &mut InputState,
+    surface: &mut GlutinSurface,
+    renderer: &mut Renderer,
+) {
+    surface.ctx.resize(physical_size);
+    renderer.update_resolution(physical_size.width, physical_size.height, scale_factor);
+
+    let logical_size = physical_size.to_logical::<f32>(scale_factor);
+    input_state.viewport_size = Vector2::new(logical_size.width, logical_size.height);
+    input_state.scale_factor = scale_factor;
+}
+
 fn handle_window_event(
     event: WindowEvent,
     input_state: &mut InputState,
@@ -26,14 +49,37 @@ fn handle_window_event(
 ) -> Option<ControlFlow> {
     match event {
         WindowEvent::Resized(physical_size) => {
-            surface.ctx.resize(physical_size);
-            renderer.update_resolution(physical_size.width, physical_size.height);
+            let scale_factor = input_state.scale_factor;
+            resize(physical_size, scale_factor, input_state, surface, renderer);
+            None
+        }
+        // Moving the window to a monitor with a different scale factor resizes it as well.
+        WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+        } => {
+            resize(
+                *new_inner_size,
+                scale_factor,
+                input_state,
+                surface,
+                renderer,
+            );
             None
         }
         WindowEvent::CloseRequested => Some(ControlFlow::Exit),
         WindowEvent::CursorMoved { position, .. } => {
-            input_state.absolute_mouse_position =
-                Vector2::new(position.x as f32, position.y as f32);
+            let position = position.to_logical::<f32>(input_state.scale_factor);
+            input_state.absolute_mouse_position = Vector2::new(position.x, position.y);
+            None
+        }
+        WindowEvent::ReceivedCharacter(c) => {
+            input_state.typed.push(c);
+            None
+        }
+        WindowEvent::ModifiersChanged(modifiers) => {
+            input_state.shift = modifiers.shift();
+            input_state.ctrl = modifiers.ctrl();
             None
         }
         WindowEvent::KeyboardInput { input, .. } => match input.virtual_keycode {
@@ -53,16 +99,144 @@ fn handle_window_event(
                     .push(InputAction::PlaceHex(HexKind::Marked));
                 None
             }
+            Some(VirtualKeyCode::F1) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::ToggleFrameStats);
+                None
+            }
+            Some(VirtualKeyCode::F10) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::ToggleMenu);
+                None
+            }
             Some(VirtualKeyCode::F2) => {
                 input_state.action_queue.push(InputAction::RingDebug);
                 None
             }
+            Some(VirtualKeyCode::F3) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::CheckUniqueness);
+                None
+            }
+            Some(VirtualKeyCode::F4) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::RateDifficulty);
+                None
+            }
+            Some(VirtualKeyCode::F5) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::GenerateLevel);
+                None
+            }
+            Some(VirtualKeyCode::F6) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::PruneLevel);
+                None
+            }
+            Some(VirtualKeyCode::F7) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::StampShape);
+                None
+            }
+            Some(VirtualKeyCode::F8) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::NextStampShape);
+                None
+            }
+            Some(VirtualKeyCode::F9) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::StartDaily);
+                None
+            }
+            Some(VirtualKeyCode::Tab) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::TogglePlay);
+                None
+            }
             Some(VirtualKeyCode::R) if input.state == ElementState::Pressed => {
                 input_state.action_queue.push(InputAction::ToggleRevealed);
                 None
             }
+            Some(VirtualKeyCode::F) if input.state == ElementState::Pressed => {
+                let mode = input_state.select_mode();
+                input_state
+                    .action_queue
+                    .push(InputAction::FloodSelect(mode));
+                None
+            }
+            Some(VirtualKeyCode::A) if input.state == ElementState::Pressed && input_state.ctrl => {
+                input_state.action_queue.push(InputAction::SelectAll);
+                None
+            }
+            Some(VirtualKeyCode::C) if input.state == ElementState::Pressed && input_state.ctrl => {
+                input_state.action_queue.push(InputAction::Copy);
+                None
+            }
+            Some(VirtualKeyCode::S) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::NextSymmetry);
+                None
+            }
+            Some(VirtualKeyCode::M) if input.state == ElementState::Pressed => {
+                input_state
+                    .action_queue
+                    .push(InputAction::ToggleReduceMotion);
+                None
+            }
+            Some(VirtualKeyCode::T) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::NextTheme);
+                None
+            }
+            Some(VirtualKeyCode::C) if input.state == ElementState::Pressed => {
+                input_state
+                    .action_queue
+                    .push(InputAction::SetSymmetryCenter);
+                None
+            }
+            Some(VirtualKeyCode::V) if input.state == ElementState::Pressed && input_state.ctrl => {
+                input_state.action_queue.push(InputAction::Paste);
+                None
+            }
+            Some(VirtualKeyCode::Up) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::PreviousProperty);
+                None
+            }
+            Some(VirtualKeyCode::Down) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::NextProperty);
+                None
+            }
+            Some(VirtualKeyCode::Left) if input.state == ElementState::Pressed => {
+                input_state
+                    .action_queue
+                    .push(InputAction::ChangeProperty { forward: false });
+                None
+            }
+            Some(VirtualKeyCode::Right) if input.state == ElementState::Pressed => {
+                input_state
+                    .action_queue
+                    .push(InputAction::ChangeProperty { forward: true });
+                None
+            }
+            Some(VirtualKeyCode::Delete) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::DeleteSelection);
+                None
+            }
+            Some(VirtualKeyCode::Escape) if input.state == ElementState::Pressed => {
+                input_state.action_queue.push(InputAction::ClearSelection);
+                None
+            }
             _ => None,
         },
+        WindowEvent::DroppedFile(path) => {
+            input_state.action_queue.push(InputAction::LoadMask(path));
+            None
+        }
+        WindowEvent::MouseInput { state, button, .. } => {
+            let button = match button {
+                MouseButton::Left => PointerButton::Primary,
+                MouseButton::Right => PointerButton::Secondary,
+                _ => return None,
+            };
+
+            let action = match state {
+                ElementState::Pressed => {
+                    InputAction::PointerDown(button, input_state.select_mode())
+                }
+                ElementState::Released => InputAction::PointerUp(button),
+            };
+
+            input_state.action_queue.push(action);
+            None
+        }
         _ => None,
     }
 }
@@ -73,32 +247,62 @@ fn main() {
         .with_inner_size(LogicalSize::new(1600.0, 900.0));
     let (mut surface, event_loop) = GlutinSurface::new_gl33(window_builder, 8).unwrap();
 
-    let mut renderer = Renderer::new(&mut surface);
+    let scale_factor = surface.ctx.window().scale_factor();
+    let mut renderer = Renderer::new(&mut surface, scale_factor);
 
     let mut input_state = InputState::default();
+    let [width, height] = surface.size();
+    let logical_size = PhysicalSize::new(width, height).to_logical::<f32>(scale_factor);
+    input_state.viewport_size = Vector2::new(logical_size.width, logical_size.height);
+    input_state.scale_factor = scale_factor;
     let mut game_state = GameState::new();
 
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-
-        match event {
-            Event::NewEvents(StartCause::Init) => *control_flow = ControlFlow::Wait,
-            Event::LoopDestroyed => return,
-            Event::WindowEvent { event, .. } => {
-                if let Some(cf) =
-                    handle_window_event(event, &mut input_state, &mut surface, &mut renderer)
-                {
-                    *control_flow = cf;
-                }
+    // `--theme <name or file>` picks the colors to start with.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--theme") {
+        match args.get(index + 1).map(|name| Theme::load(name)) {
+            Some(Ok(theme)) => game_state.theme = theme,
+            Some(Err(message)) => eprintln!("error: {}", message),
+            None => eprintln!("error: --theme needs a value"),
+        }
+    }
+
+    game_state.animations.reduce_motion = args.iter().any(|arg| arg == "--reduce-motion");
+
+    // The loop sleeps until there's input or the game asks to be woken, and only draws frames
+    // after something changed.
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::NewEvents(StartCause::Init) => *control_flow = ControlFlow::Wait,
+        Event::LoopDestroyed => (),
+        Event::WindowEvent { event, .. } => {
+            if let Some(cf) =
+                handle_window_event(event, &mut input_state, &mut surface, &mut renderer)
+            {
+                *control_flow = cf;
             }
-            Event::MainEventsCleared => {
-                update(&mut game_state, &mut input_state);
+        }
+        Event::MainEventsCleared => {
+            let start = Instant::now();
+
+            if update(&mut game_state, &mut input_state) {
                 surface.ctx.window().request_redraw();
             }
-            Event::RedrawRequested(_) => {
-                renderer.render(&game_state, &mut surface);
+
+            let now = Instant::now();
+            game_state.frame_stats.busy(now - start);
+
+            if *control_flow != ControlFlow::Exit {
+                *control_flow = match next_update(&game_state, now) {
+                    Some(time) => ControlFlow::WaitUntil(time),
+                    None => ControlFlow::Wait,
+                };
             }
-            _ => (),
         }
+        Event::RedrawRequested(_) => {
+            let start = Instant::now();
+            renderer.render(&build_frame(&game_state), &mut surface);
+            game_state.frame_stats.frame(start.elapsed());
+        }
+        _ => (),
     });
 }
@@ -0,0 +1,48 @@
+//! The menu opened over the level, for naming the level, picking a theme and starting play.
+
+use cgmath::Vector2;
+
+use opencells_rs::theme::Theme;
+
+use crate::{game::GameState, input::InputAction, ui::Ui};
+
+const MENU_SIZE: Vector2<f32> = Vector2::new(420.0, 440.0);
+
+/// Shows the menu, queueing the actions picked from it.
+pub fn show(ui: &mut Ui, state: &mut GameState, actions: &mut Vec<InputAction>) {
+    let mut layout = ui.dialog(state.viewport_size, MENU_SIZE, "OpenCells");
+
+    ui.label(&mut layout, "Level title");
+    ui.text_input(&mut layout, "title", &mut state.level.info.title);
+
+    ui.label(&mut layout, "Theme");
+    let selected = Theme::BUILT_IN
+        .iter()
+        .position(|name| *name == state.theme.name);
+
+    if let Some(index) = ui.list(&mut layout, "theme", &Theme::BUILT_IN, selected) {
+        state.theme = Theme::built_in(Theme::BUILT_IN[index]).unwrap();
+    }
+
+    let play = if state.is_playing() { "Edit" } else { "Play" };
+    let buttons = layout.row(2);
+
+    if ui.button_in(buttons[0], "play", play) {
+        actions.push(InputAction::TogglePlay);
+        state.menu_open = false;
+    }
+
+    if ui.button_in(buttons[1], "daily", "Daily level") {
+        actions.push(InputAction::StartDaily);
+        state.menu_open = false;
+    }
+
+    if !state.is_playing() && ui.button(&mut layout, "generate", "Generate level") {
+        actions.push(InputAction::GenerateLevel);
+        state.menu_open = false;
+    }
+
+    if ui.button(&mut layout, "close", "Close") {
+        state.menu_open = false;
+    }
+}
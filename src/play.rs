@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use crate::{hexagon::Axial, level::Level};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guess {
+    Correct,
+    /// The guess was correct and every cell is now revealed.
+    Completed,
+    Mistake,
+    /// There's no hidden cell at the position, or the level is already completed.
+    Ignored,
+}
+
+/// A run through a level, where the player reveals cells by guessing their kind.
+#[derive(Debug, Clone)]
+pub struct PlaySession {
+    pub mistakes: u32,
+    pub started: Instant,
+    pub completed: bool,
+}
+
+impl PlaySession {
+    /// Starts playing a level, hiding every cell that isn't revealed at the start.
+    pub fn start(level: &Level) -> PlaySession {
+        for cell in level.cells.values() {
+            cell.reset_revealed();
+        }
+
+        PlaySession {
+            mistakes: 0,
+            started: Instant::now(),
+            completed: level.cells.values().all(|cell| cell.is_revealed()),
+        }
+    }
+
+    pub fn guess(&mut self, level: &Level, position: Axial, marked: bool) -> Guess {
+        if self.completed {
+            return Guess::Ignored;
+        }
+
+        let cell = match level.cells.get(&position) {
+            Some(cell) if !cell.is_revealed() => cell,
+            _ => return Guess::Ignored,
+        };
+
+        if cell.hex.is_marked() != marked {
+            self.mistakes += 1;
+            return Guess::Mistake;
+        }
+
+        cell.reveal();
+
+        if level.cells.values().all(|cell| cell.is_revealed()) {
+            self.completed = true;
+            Guess::Completed
+        } else {
+            Guess::Correct
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
@@ -0,0 +1,434 @@
+//! A minimal PNG encoder for 8-bit RGB images, so screenshots and thumbnails can be written
+//! without an image library. Image data is compressed with fixed Huffman codes, matching runs
+//! against the previous pixel and the row above, which is enough for flat-shaded boards.
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const BYTES_PER_PIXEL: usize = 3;
+
+/// Longest match and furthest distance deflate allows.
+const MAX_MATCH: usize = 258;
+const MAX_DISTANCE: usize = 32768;
+const MIN_MATCH: usize = 3;
+/// The most a stored block can hold.
+const MAX_STORED: usize = 65535;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Encodes `pixels`, given row by row as red, green and blue bytes.
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let stride = width as usize * BYTES_PER_PIXEL;
+    assert_eq!(
+        pixels.len(),
+        stride * height as usize,
+        "pixel data doesn't match the image size"
+    );
+
+    // Every row starts with the filter type, which is always "none".
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGB, deflate, no interlacing.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_compress(&raw, stride + 1));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for chunk in data.chunks(4096) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+/// Writes bits starting from the least significant one, as deflate expects.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are stored starting from their most significant bit.
+    fn code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.bits(reversed, length);
+    }
+
+    fn symbol(&mut self, symbol: u32) {
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let index = LENGTH_BASE
+        .iter()
+        .rposition(|base| *base as usize <= length)
+        .unwrap();
+    writer.symbol(257 + index as u32);
+    writer.bits(
+        (length - LENGTH_BASE[index] as usize) as u32,
+        LENGTH_EXTRA[index] as u32,
+    );
+
+    let index = DISTANCE_BASE
+        .iter()
+        .rposition(|base| *base as usize <= distance)
+        .unwrap();
+    writer.code(index as u32, 5);
+    writer.bits(
+        (distance - DISTANCE_BASE[index] as usize) as u32,
+        DISTANCE_EXTRA[index] as u32,
+    );
+}
+
+fn match_length(data: &[u8], position: usize, distance: usize) -> usize {
+    if distance > position || distance > MAX_DISTANCE {
+        return 0;
+    }
+
+    data[position..]
+        .iter()
+        .take(MAX_MATCH)
+        .zip(&data[position - distance..])
+        .take_while(|(byte, earlier)| byte == earlier)
+        .count()
+}
+
+/// Compresses `data` into a zlib stream, using a single fixed Huffman block unless storing
+/// the bytes as they are comes out smaller, as it does for noisy images.
+fn zlib_compress(data: &[u8], stride: usize) -> Vec<u8> {
+    // Deflate with a 32 KiB window and no preset dictionary.
+    let mut stream = vec![0x78, 0x01];
+
+    let fixed = deflate_fixed(data, stride);
+    let blocks = data.len().div_ceil(MAX_STORED).max(1);
+
+    if fixed.len() <= data.len() + blocks * 5 {
+        stream.extend_from_slice(&fixed);
+    } else {
+        deflate_stored(&mut stream, data);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// A single final block using fixed codes, looking for repeats of the previous pixel and of the
+/// row `stride` bytes back.
+fn deflate_fixed(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let mut position = 0;
+
+    while position < data.len() {
+        let (length, distance) = [BYTES_PER_PIXEL, stride]
+            .iter()
+            .map(|distance| (match_length(data, position, *distance), *distance))
+            .max_by_key(|(length, _)| *length)
+            .unwrap();
+
+        if length >= MIN_MATCH {
+            write_match(&mut writer, length, distance);
+            position += length;
+        } else {
+            writer.symbol(data[position] as u32);
+            position += 1;
+        }
+    }
+
+    writer.symbol(256);
+    writer.finish()
+}
+
+/// Stored blocks of at most 64 KiB each, the last one marked final.
+fn deflate_stored(stream: &mut Vec<u8>, data: &[u8]) {
+    let mut chunks = data.chunks(MAX_STORED).peekable();
+
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        return;
+    }
+
+    while let Some(chunk) = chunks.next() {
+        let length = chunk.len() as u16;
+
+        stream.push(chunks.peek().is_none() as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads back the stored and fixed Huffman blocks written above.
+    fn inflate(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(&stream[..2], &[0x78, 0x01]);
+
+        let mut reader = BitReader {
+            bytes: &stream[2..],
+            position: 0,
+        };
+        let mut data = Vec::new();
+
+        loop {
+            let last = reader.bits(1) == 1;
+
+            match reader.bits(2) {
+                0 => {
+                    reader.align();
+                    let length = reader.bits(16) as usize;
+                    let inverse = reader.bits(16) as usize;
+                    assert_eq!(length, !inverse & 0xffff);
+
+                    for _ in 0..length {
+                        data.push(reader.bits(8) as u8);
+                    }
+                }
+                1 => loop {
+                    let symbol = reader.symbol();
+
+                    match symbol {
+                        0..=255 => data.push(symbol as u8),
+                        256 => break,
+                        _ => {
+                            let index = symbol as usize - 257;
+                            let length = LENGTH_BASE[index] as usize
+                                + reader.bits(LENGTH_EXTRA[index] as u32) as usize;
+
+                            let index = reader.code(5) as usize;
+                            let distance = DISTANCE_BASE[index] as usize
+                                + reader.bits(DISTANCE_EXTRA[index] as u32) as usize;
+
+                            for _ in 0..length {
+                                data.push(data[data.len() - distance]);
+                            }
+                        }
+                    }
+                },
+                kind => panic!("unexpected block type {}", kind),
+            }
+
+            if last {
+                break;
+            }
+        }
+
+        reader.align();
+        let trailer = &reader.bytes[reader.position / 8..];
+        assert_eq!(trailer, &adler32(&data).to_be_bytes());
+
+        data
+    }
+
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, bit| {
+                let byte = self.bytes[self.position / 8];
+                let set = (byte >> (self.position % 8)) & 1;
+                self.position += 1;
+                value | (set as u32) << bit
+            })
+        }
+
+        fn code(&mut self, length: u32) -> u32 {
+            (0..length).fold(0, |code, _| (code << 1) | self.bits(1))
+        }
+
+        fn align(&mut self) {
+            self.position = self.position.div_ceil(8) * 8;
+        }
+
+        fn symbol(&mut self) -> u32 {
+            let code = self.code(7);
+
+            match code {
+                0..=0x17 => code + 256,
+                _ => {
+                    let code = (code << 1) | self.bits(1);
+
+                    match code {
+                        0x30..=0xbf => code - 0x30,
+                        0xc0..=0xc7 => code - 0xc0 + 280,
+                        _ => ((code << 1) | self.bits(1)) - 0x190 + 144,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bytes that don't repeat, from a small linear congruential generator.
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state = 12345u32;
+
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crc32_matches_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+    }
+
+    #[test]
+    fn adler32_matches_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(&[0xff; 10000]), adler32_slow(&[0xff; 10000]));
+    }
+
+    fn adler32_slow(data: &[u8]) -> u32 {
+        let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+            let a = (a + *byte as u32) % 65521;
+            (a, (b + a) % 65521)
+        });
+        (b << 16) | a
+    }
+
+    #[test]
+    fn fixed_blocks_round_trip() {
+        let stride = 3 * 40;
+        let mut data = Vec::new();
+
+        for row in 0..30u8 {
+            data.extend((0..stride).map(|x| if x < 60 { row } else { x as u8 / 7 }));
+        }
+
+        let stream = zlib_compress(&data, stride);
+        assert_eq!(stream[2] & 0b111, 0b011);
+        assert!(stream.len() < data.len() / 4);
+        assert_eq!(inflate(&stream), data);
+    }
+
+    #[test]
+    fn stored_blocks_round_trip() {
+        for length in [0, 1, 1000, MAX_STORED, MAX_STORED + 1, 150_000].iter() {
+            let data = noise(*length);
+            let stream = zlib_compress(&data, 300);
+
+            if data.len() >= 1000 {
+                assert_eq!(stream[2] & 0b110, 0, "length {}", length);
+            }
+            assert_eq!(inflate(&stream), data, "length {}", length);
+        }
+    }
+
+    #[test]
+    fn png_has_chunks_with_checksums() {
+        let png = encode_png(2, 1, &[255, 0, 0, 0, 0, 255]);
+
+        assert_eq!(&png[..8], &SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(
+            &png[png.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+
+        // Each row starts with its filter byte.
+        let idat = &png[33..];
+        let length = u32::from_be_bytes([idat[0], idat[1], idat[2], idat[3]]) as usize;
+        assert_eq!(&idat[4..8], b"IDAT");
+        assert_eq!(inflate(&idat[8..8 + length]), vec![0, 255, 0, 0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    hexagon::Axial,
+    level::{Hex, HintModifier, Level, LineHint},
+    solver::solve,
+};
+
+/// Information taken away from a level, with what it was so it can be put back.
+#[derive(Debug, Clone, Copy)]
+pub enum Removal {
+    StartRevealed(Axial),
+    LineHint(Axial, LineHint),
+    NeighborCount(Axial),
+    AroundCount(Axial),
+    NeighborModifier(Axial, HintModifier),
+    LineModifier(Axial, HintModifier),
+}
+
+#[derive(Debug)]
+pub struct Unsolvable {
+    pub stuck: Vec<Axial>,
+}
+
+/// Greedily hides start reveals and hints for as long as the deduction solver can still finish
+/// the level. Start reveals are tried first, then line hints, cell counts and finally modifiers.
+pub fn prune(level: &mut Level) -> Result<Vec<Removal>, Unsolvable> {
+    let trace = solve(level);
+
+    if !trace.is_solved() {
+        return Err(Unsolvable { stuck: trace.stuck });
+    }
+
+    let mut removed = Vec::new();
+
+    for removal in candidates(level) {
+        if !is_present(level, removal) {
+            continue;
+        }
+
+        apply(level, removal, true);
+
+        if solve(level).is_solved() {
+            removed.push(removal);
+        } else {
+            apply(level, removal, false);
+        }
+    }
+
+    Ok(removed)
+}
+
+fn candidates(level: &Level) -> Vec<Removal> {
+    let mut positions: Vec<Axial> = level.cells.keys().copied().collect();
+    positions.sort_by_key(|position| (position.x, position.y));
+
+    let mut origins: Vec<Axial> = level.line_hints.keys().copied().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    let mut candidates = Vec::new();
+
+    candidates.extend(
+        positions
+            .iter()
+            .filter(|position| level.cells[position].start_revealed)
+            .map(|position| Removal::StartRevealed(*position)),
+    );
+
+    candidates.extend(
+        origins
+            .iter()
+            .map(|origin| Removal::LineHint(*origin, level.line_hints[origin])),
+    );
+
+    for position in &positions {
+        match level.cells[position].hex {
+            Hex::Empty {
+                show_neighbor_count: true,
+                ..
+            } => candidates.push(Removal::NeighborCount(*position)),
+            Hex::Marked { show_around: true } => candidates.push(Removal::AroundCount(*position)),
+            _ => {}
+        }
+    }
+
+    for position in &positions {
+        if let Hex::Empty { modifier, .. } = level.cells[position].hex {
+            if modifier != HintModifier::Plain {
+                candidates.push(Removal::NeighborModifier(*position, modifier));
+            }
+        }
+    }
+
+    for origin in &origins {
+        let modifier = level.line_hints[origin].modifier;
+
+        if modifier != HintModifier::Plain {
+            candidates.push(Removal::LineModifier(*origin, modifier));
+        }
+    }
+
+    candidates
+}
+
+/// Whether the information is still shown, as an earlier removal can hide it.
+fn is_present(level: &Level, removal: Removal) -> bool {
+    match removal {
+        Removal::NeighborModifier(position, _) => matches!(
+            level.cells[&position].hex,
+            Hex::Empty {
+                show_neighbor_count: true,
+                ..
+            }
+        ),
+        Removal::LineModifier(origin, _) => level.line_hints.contains_key(&origin),
+        _ => true,
+    }
+}
+
+fn apply(level: &mut Level, removal: Removal, remove: bool) {
+    match removal {
+        Removal::StartRevealed(position) => {
+            level.cells.get_mut(&position).unwrap().start_revealed = !remove;
+        }
+        Removal::LineHint(origin, hint) => {
+            if remove {
+                level.line_hints.remove(&origin);
+            } else {
+                level.line_hints.insert(origin, hint);
+            }
+        }
+        Removal::NeighborCount(position) => {
+            if let Hex::Empty {
+                show_neighbor_count,
+                ..
+            } = &mut level.cells.get_mut(&position).unwrap().hex
+            {
+                *show_neighbor_count = !remove;
+            }
+        }
+        Removal::AroundCount(position) => {
+            if let Hex::Marked { show_around } = &mut level.cells.get_mut(&position).unwrap().hex {
+                *show_around = !remove;
+            }
+        }
+        Removal::NeighborModifier(position, previous) => {
+            if let Hex::Empty { modifier, .. } = &mut level.cells.get_mut(&position).unwrap().hex {
+                *modifier = if remove {
+                    HintModifier::Plain
+                } else {
+                    previous
+                };
+            }
+        }
+        Removal::LineModifier(origin, previous) => {
+            if let Some(hint) = level.line_hints.get_mut(&origin) {
+                hint.modifier = if remove {
+                    HintModifier::Plain
+                } else {
+                    previous
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formats::{write_level, Format},
+        generator::{generate, GeneratorParams, HintKinds},
+        test_levels::level,
+    };
+
+    fn generated(seed: u64) -> Level {
+        let params = GeneratorParams {
+            hint_kinds: HintKinds {
+                neighbors: true,
+                around: true,
+                lines: true,
+                modifiers: true,
+            },
+            ..GeneratorParams::default()
+        };
+
+        generate(seed, &params)
+    }
+
+    fn text(level: &Level) -> String {
+        write_level(level, Format::Native).unwrap()
+    }
+
+    /// Every cell with whether it's marked, as the solver deduces it or as revealed at start.
+    fn solution(level: &Level) -> Vec<(Axial, bool)> {
+        let mut cells: Vec<(Axial, bool)> = solve(level)
+            .steps
+            .iter()
+            .flat_map(|step| &step.deductions)
+            .map(|deduction| (deduction.cell, deduction.marked))
+            .chain(
+                level
+                    .cells
+                    .iter()
+                    .filter(|(_, cell)| cell.start_revealed)
+                    .map(|(position, cell)| (*position, cell.hex.is_marked())),
+            )
+            .collect();
+        cells.sort_by_key(|(position, _)| (position.x, position.y));
+        cells
+    }
+
+    /// Everything shown in `before` that `after` hides, worded like `describe`.
+    fn hidden(before: &Level, after: &Level) -> Vec<String> {
+        let mut hidden = Vec::new();
+
+        for (position, cell) in &before.cells {
+            let now = &after.cells[position];
+
+            if cell.start_revealed && !now.start_revealed {
+                hidden.push(format!("reveal {:?}", position));
+            }
+
+            match (cell.hex, now.hex) {
+                (
+                    Hex::Empty {
+                        show_neighbor_count: true,
+                        modifier,
+                    },
+                    Hex::Empty {
+                        show_neighbor_count,
+                        modifier: now,
+                    },
+                ) => {
+                    if !show_neighbor_count {
+                        hidden.push(format!("count {:?}", position));
+                    } else if modifier != now {
+                        assert_eq!(now, HintModifier::Plain);
+                        hidden.push(format!("modifier {:?} {:?}", position, modifier));
+                    }
+                }
+                (Hex::Marked { show_around: true }, Hex::Marked { show_around: false }) => {
+                    hidden.push(format!("around {:?}", position));
+                }
+                _ => {}
+            }
+        }
+
+        for (origin, hint) in &before.line_hints {
+            match after.line_hints.get(origin) {
+                None => hidden.push(format!("line {:?}", origin)),
+                Some(now) if now.modifier != hint.modifier => {
+                    assert_eq!(now.modifier, HintModifier::Plain);
+                    hidden.push(format!("line modifier {:?} {:?}", origin, hint.modifier));
+                }
+                Some(_) => {}
+            }
+        }
+
+        hidden.sort();
+        hidden
+    }
+
+    fn describe(removal: &Removal) -> String {
+        match removal {
+            Removal::StartRevealed(position) => format!("reveal {:?}", position),
+            Removal::LineHint(origin, _) => format!("line {:?}", origin),
+            Removal::NeighborCount(position) => format!("count {:?}", position),
+            Removal::AroundCount(position) => format!("around {:?}", position),
+            Removal::NeighborModifier(position, modifier) => {
+                format!("modifier {:?} {:?}", position, modifier)
+            }
+            Removal::LineModifier(origin, modifier) => {
+                format!("line modifier {:?} {:?}", origin, modifier)
+            }
+        }
+    }
+
+    #[test]
+    fn pruned_levels_keep_their_solution() {
+        for seed in 0..6 {
+            let original = generated(seed);
+            let mut pruned = generated(seed);
+            prune(&mut pruned).unwrap();
+
+            assert!(solve(&pruned).is_solved(), "seed {}", seed);
+            let solved = solution(&pruned);
+            assert_eq!(solved.len(), original.cells.len(), "seed {}", seed);
+            assert_eq!(solved, solution(&original), "seed {}", seed);
+            for (position, marked) in solved {
+                assert_eq!(marked, original.is_marked(position), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn report_lists_exactly_what_was_hidden() {
+        for seed in 0..6 {
+            let original = generated(seed);
+            let mut pruned = generated(seed);
+            let removed = prune(&mut pruned).unwrap();
+
+            assert!(!removed.is_empty(), "seed {}", seed);
+
+            let mut reported: Vec<String> = removed.iter().map(describe).collect();
+            reported.sort();
+            assert_eq!(reported, hidden(&original, &pruned), "seed {}", seed);
+
+            // Putting everything back in reverse order restores the level.
+            for removal in removed.iter().rev() {
+                apply(&mut pruned, *removal, false);
+            }
+            assert_eq!(text(&pruned), text(&original), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn unsolvable_levels_are_left_alone() {
+        let items = "cell 0 0 empty number revealed
+                     cell 1 0 marked
+                     cell 0 1 empty";
+        let mut ambiguous = level(items);
+
+        let error = prune(&mut ambiguous).unwrap_err();
+
+        assert_eq!(error.stuck, [Axial::new(0, 1), Axial::new(1, 0)]);
+        assert_eq!(text(&ambiguous), text(&level(items)));
+    }
+}
@@ -0,0 +1,504 @@
+//! Draws a `Scene` on the CPU, matching what the windowed renderer shows closely enough for
+//! screenshots, thumbnails and comparing against saved images.
+
+use std::fmt;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::{
+    png::encode_png,
+    scene::{
+        Anchor, DrawHex, DrawRect, DrawText, Scene, FONT, HIDDEN_PATTERN, MARKED_PATTERN, SELECTED,
+        START_REVEALED,
+    },
+};
+
+// Keep in sync with hex-fs.glsl.
+const INDICATOR_RADIUS: f32 = 3.8;
+/// Distance between stripes on hidden cells, relative to the scene's hex size.
+const STRIPE_PERIOD: f32 = 0.3;
+/// Where the ring on marked cells starts and ends, in edginess.
+const RING_INNER: f32 = 0.45;
+const RING_OUTER: f32 = 0.55;
+/// Corners of the drawn hexagon, relative to the scene's hex size, as in the hexagon mesh.
+const HEX_RADIUS: f32 = 0.95;
+
+const SQRT_3_2: f32 = 0.866_025_4;
+
+/// Samples per pixel along each axis, for smooth hexagon edges.
+const SUPERSAMPLING: u32 = 2;
+
+/// The most pixels an image or a single piece of text may cover, far more than any level needs.
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// A scene or a piece of text in it too large to draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RasterError {
+    pub width: u64,
+    pub height: u64,
+}
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} pixels is more than the {} that can be drawn",
+            self.width, self.height, MAX_PIXELS
+        )
+    }
+}
+
+/// How many pixels a `width` by `height` area has, if it isn't too large to draw.
+fn pixel_count(width: u64, height: u64) -> Result<usize, RasterError> {
+    width
+        .checked_mul(height)
+        .filter(|count| *count <= MAX_PIXELS && width <= MAX_PIXELS && height <= MAX_PIXELS)
+        .map(|count| count as usize)
+        .ok_or(RasterError { width, height })
+}
+
+/// An 8-bit RGB image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Red, green and blue bytes, row by row from the top.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = (y * self.width + x) as usize * 3;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+        ]
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, &self.pixels)
+    }
+}
+
+/// Colors in linear 0 to 1 floats while drawing, so blending doesn't lose precision.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vector3<f32>>,
+}
+
+impl Canvas {
+    fn get(&self, x: u32, y: u32) -> Vector3<f32> {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: Vector3<f32>) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    fn blend(&mut self, x: i32, y: i32, color: Vector3<f32>, alpha: f32) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+
+        let (x, y) = (x as u32, y as u32);
+        let alpha = alpha.clamp(0.0, 1.0);
+        let blended = self.get(x, y) * (1.0 - alpha) + color * alpha;
+        self.set(x, y, blended);
+    }
+
+    fn into_image(self) -> Image {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self
+                .pixels
+                .iter()
+                .flat_map(|color| vec![channel(color.x), channel(color.y), channel(color.z)])
+                .collect(),
+        }
+    }
+}
+
+pub fn rasterize(scene: &Scene) -> Result<Image, RasterError> {
+    let width = scene.size.x.round().max(0.0) as u32;
+    let height = scene.size.y.round().max(0.0) as u32;
+    let [red, green, blue, _] = scene.clear_color;
+
+    let count = pixel_count(width as u64, height as u64)?;
+    let mut canvas = Canvas {
+        width,
+        height,
+        pixels: vec![Vector3::new(red, green, blue); count],
+    };
+
+    for hex in &scene.hexes {
+        draw_hex(&mut canvas, scene, hex);
+    }
+
+    let font = FontRef::try_from_slice(FONT).expect("the bundled font is valid");
+
+    for text in &scene.texts {
+        draw_text(&mut canvas, &font, text)?;
+    }
+
+    for rect in &scene.rects {
+        draw_rect(&mut canvas, rect);
+    }
+
+    for text in &scene.overlay_texts {
+        draw_text(&mut canvas, &font, text)?;
+    }
+
+    Ok(canvas.into_image())
+}
+
+/// Fills the pixels whose centers are inside the rectangle.
+fn draw_rect(canvas: &mut Canvas, rect: &DrawRect) {
+    let color = Vector3::new(rect.color[0], rect.color[1], rect.color[2]);
+    let (min, max) = (rect.position, rect.position + rect.size);
+
+    for y in (min.y - 0.5).ceil() as i32..(max.y - 0.5).ceil() as i32 {
+        for x in (min.x - 0.5).ceil() as i32..(max.x - 0.5).ceil() as i32 {
+            canvas.blend(x, y, color, rect.color[3]);
+        }
+    }
+}
+
+/// Whether a point is inside a flat-top hexagon of the given radius around the origin.
+fn in_hexagon(point: Vector2<f32>, radius: f32) -> bool {
+    let (x, y) = (point.x.abs(), point.y.abs());
+    let half_height = SQRT_3_2 * radius;
+    y <= half_height && SQRT_3_2 * x + 0.5 * y <= half_height
+}
+
+/// How far a point is from the center towards the edge of the drawn hexagon, from 0 at the
+/// center to 1 on the edge, the same way the mesh interpolates it.
+fn edginess(offset: Vector2<f32>, size: f32) -> f32 {
+    let apothem = SQRT_3_2 * HEX_RADIUS * size;
+
+    let normals = [
+        Vector2::new(SQRT_3_2, 0.5),
+        Vector2::new(0.0, 1.0),
+        Vector2::new(-SQRT_3_2, 0.5),
+    ];
+
+    normals
+        .iter()
+        .map(|normal| offset.dot(*normal).abs())
+        .fold(0.0, f32::max)
+        / apothem
+}
+
+/// The color of a point inside a hexagon, as `hex-fs.glsl` shades it.
+fn shade(scene: &Scene, hex: &DrawHex, offset: Vector2<f32>, edginess: f32) -> Vector3<f32> {
+    let size = scene.hex_size;
+    let mut color = hex.color;
+
+    color *= 1.0 + (scene.hover_brightness - 1.0) * hex.hover;
+
+    let period = STRIPE_PERIOD * size;
+    if hex.flags & HIDDEN_PATTERN != 0 && (offset.x + offset.y).rem_euclid(period) < period / 3.0 {
+        color *= 0.7;
+    }
+
+    if hex.flags & MARKED_PATTERN != 0 && edginess > RING_INNER && edginess < RING_OUTER {
+        color = scene.border_color;
+    }
+
+    if hex.flags & SELECTED != 0 {
+        color = color * 0.5 + Vector3::new(0.5, 0.5, 0.5);
+    }
+
+    let indicator_center = Vector2::new(0.0, SQRT_3_2 * size - 16.0);
+
+    if hex.flags & START_REVEALED != 0 && in_hexagon(offset - indicator_center, INDICATOR_RADIUS) {
+        return Vector3::new(1.0, 1.0, 1.0);
+    }
+
+    let border = if edginess > 0.95 {
+        0.95
+    } else if edginess < 0.8 {
+        0.0
+    } else {
+        edginess.powf(10.0).clamp(0.0, 1.0)
+    };
+
+    color * (1.0 - border) + scene.border_color * border
+}
+
+fn draw_hex(canvas: &mut Canvas, scene: &Scene, hex: &DrawHex) {
+    // A hexagon turned edge on covers nothing.
+    if hex.stretch.x <= f32::EPSILON || hex.stretch.y <= f32::EPSILON {
+        return;
+    }
+
+    let size = scene.hex_size;
+    let radius = HEX_RADIUS * size * hex.stretch.x;
+    let half_height = SQRT_3_2 * HEX_RADIUS * size * hex.stretch.y;
+
+    let min_x = (hex.center.x - radius).floor().max(0.0) as u32;
+    let min_y = (hex.center.y - half_height).floor().max(0.0) as u32;
+    let max_x = ((hex.center.x + radius).ceil().max(0.0) as u32).min(canvas.width);
+    let max_y = ((hex.center.y + half_height).ceil().max(0.0) as u32).min(canvas.height);
+
+    let samples = (SUPERSAMPLING * SUPERSAMPLING) as f32;
+    let step = 1.0 / SUPERSAMPLING as f32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let background = canvas.get(x, y);
+            let mut total = Vector3::new(0.0, 0.0, 0.0);
+
+            for sample_y in 0..SUPERSAMPLING {
+                for sample_x in 0..SUPERSAMPLING {
+                    let sample = Vector2::new(
+                        x as f32 + (sample_x as f32 + 0.5) * step,
+                        y as f32 + (sample_y as f32 + 0.5) * step,
+                    );
+                    let offset = sample - hex.center;
+                    let unstretched =
+                        Vector2::new(offset.x / hex.stretch.x, offset.y / hex.stretch.y);
+                    let edginess = edginess(unstretched, size);
+
+                    total += if edginess <= 1.0 {
+                        shade(scene, hex, offset, edginess).map(|value| value.clamp(0.0, 1.0))
+                    } else {
+                        background
+                    };
+                }
+            }
+
+            canvas.set(x, y, total / samples);
+        }
+    }
+}
+
+/// How much of each pixel in a box is covered by text, before it's turned and blended.
+struct Coverage {
+    min: Vector2<i32>,
+    width: i32,
+    height: i32,
+    values: Vec<f32>,
+}
+
+impl Coverage {
+    fn new(min: Vector2<i32>, max: Vector2<i32>) -> Result<Coverage, RasterError> {
+        let width = (max.x as i64 - min.x as i64).max(0) as u64;
+        let height = (max.y as i64 - min.y as i64).max(0) as u64;
+        let count = pixel_count(width, height)?;
+
+        Ok(Coverage {
+            min,
+            width: width as i32,
+            height: height as i32,
+            values: vec![0.0; count],
+        })
+    }
+
+    fn get(&self, x: i32, y: i32) -> f32 {
+        let (x, y) = (x - self.min.x, y - self.min.y);
+
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return 0.0;
+        }
+
+        self.values[(y * self.width + x) as usize]
+    }
+
+    fn add(&mut self, x: i32, y: i32, coverage: f32) {
+        let (x, y) = (x - self.min.x, y - self.min.y);
+
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+
+        let value = &mut self.values[(y * self.width + x) as usize];
+        *value = (*value + coverage).min(1.0);
+    }
+
+    /// Coverage at any point, blending the four nearest pixels.
+    fn sample(&self, point: Vector2<f32>) -> f32 {
+        let x = point.x - 0.5;
+        let y = point.y - 0.5;
+        let (left, top) = (x.floor(), y.floor());
+        let (right_weight, bottom_weight) = (x - left, y - top);
+        let (left, top) = (left as i32, top as i32);
+
+        let top_row =
+            self.get(left, top) * (1.0 - right_weight) + self.get(left + 1, top) * right_weight;
+        let bottom_row = self.get(left, top + 1) * (1.0 - right_weight)
+            + self.get(left + 1, top + 1) * right_weight;
+
+        top_row * (1.0 - bottom_weight) + bottom_row * bottom_weight
+    }
+}
+
+/// Turns `offset` clockwise by `degrees`, with y pointing down.
+fn rotate(offset: Vector2<f32>, degrees: f32) -> Vector2<f32> {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Vector2::new(
+        offset.x * cos - offset.y * sin,
+        offset.x * sin + offset.y * cos,
+    )
+}
+
+/// Lays text out the way the windowed renderer's glyph brush does: top left anchored text starts
+/// its first line at the position, centered text is a single line centered on it. Turned text
+/// is laid out upright first and then turned around its position.
+fn draw_text(canvas: &mut Canvas, font: &FontRef, text: &DrawText) -> Result<(), RasterError> {
+    let coverage = text_coverage(font, text)?;
+    let color = Vector3::new(text.color[0], text.color[1], text.color[2]);
+    let alpha = text.color[3];
+
+    if text.rotation == 0.0 {
+        for y in 0..coverage.height {
+            for x in 0..coverage.width {
+                let (x, y) = (coverage.min.x + x, coverage.min.y + y);
+                canvas.blend(x, y, color, coverage.get(x, y) * alpha);
+            }
+        }
+
+        return Ok(());
+    }
+
+    let corners = [
+        Vector2::new(coverage.min.x, coverage.min.y),
+        Vector2::new(coverage.min.x + coverage.width, coverage.min.y),
+        Vector2::new(coverage.min.x, coverage.min.y + coverage.height),
+        Vector2::new(
+            coverage.min.x + coverage.width,
+            coverage.min.y + coverage.height,
+        ),
+    ]
+    .iter()
+    .map(|corner| rotate(corner.cast::<f32>().unwrap() - text.position, text.rotation))
+    .map(|offset| offset + text.position)
+    .collect::<Vec<_>>();
+
+    let min_x = corners
+        .iter()
+        .map(|corner| corner.x)
+        .fold(f32::MAX, f32::min);
+    let min_y = corners
+        .iter()
+        .map(|corner| corner.y)
+        .fold(f32::MAX, f32::min);
+    let max_x = corners
+        .iter()
+        .map(|corner| corner.x)
+        .fold(f32::MIN, f32::max);
+    let max_y = corners
+        .iter()
+        .map(|corner| corner.y)
+        .fold(f32::MIN, f32::max);
+
+    for y in min_y.floor() as i32..max_y.ceil() as i32 {
+        for x in min_x.floor() as i32..max_x.ceil() as i32 {
+            let center = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let source = rotate(center - text.position, -text.rotation) + text.position;
+            canvas.blend(x, y, color, coverage.sample(source) * alpha);
+        }
+    }
+
+    Ok(())
+}
+
+fn text_coverage(font: &FontRef, text: &DrawText) -> Result<Coverage, RasterError> {
+    let font = font.as_scaled(PxScale::from(text.size));
+    let line_height = font.ascent() - font.descent() + font.line_gap();
+
+    let line_width = |line: &str| {
+        let mut width = 0.0;
+        let mut previous = None;
+
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+
+        width
+    };
+
+    let lines: Vec<&str> = match text.anchor {
+        Anchor::TopLeft => text.text.lines().collect(),
+        Anchor::Center => vec![text.text.as_str()],
+    };
+
+    let mut outlines = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let (mut x, baseline) = match text.anchor {
+            Anchor::TopLeft => (
+                text.position.x,
+                text.position.y + font.ascent() + index as f32 * line_height,
+            ),
+            Anchor::Center => (
+                text.position.x - line_width(line) / 2.0,
+                text.position.y + (font.ascent() + font.descent()) / 2.0,
+            ),
+        };
+
+        let mut previous = None;
+
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id);
+            }
+
+            let glyph = id.with_scale_and_position(font.scale(), point(x, baseline));
+            outlines.extend(font.outline_glyph(glyph));
+
+            x += font.h_advance(id);
+            previous = Some(id);
+        }
+    }
+
+    if outlines.is_empty() {
+        return Coverage::new(Vector2::new(0, 0), Vector2::new(0, 0));
+    }
+
+    let (min, max) = outlines.iter().map(|outline| outline.px_bounds()).fold(
+        (
+            Vector2::new(i32::MAX, i32::MAX),
+            Vector2::new(i32::MIN, i32::MIN),
+        ),
+        |(min, max), bounds| {
+            (
+                Vector2::new(
+                    min.x.min(bounds.min.x as i32),
+                    min.y.min(bounds.min.y as i32),
+                ),
+                Vector2::new(
+                    max.x.max(bounds.max.x as i32),
+                    max.y.max(bounds.max.y as i32),
+                ),
+            )
+        },
+    );
+
+    let mut coverage = Coverage::new(min, max)?;
+
+    for outline in outlines {
+        let bounds = outline.px_bounds();
+
+        outline.draw(|glyph_x, glyph_y, amount| {
+            coverage.add(
+                bounds.min.x as i32 + glyph_x as i32,
+                bounds.min.y as i32 + glyph_y as i32,
+                amount,
+            );
+        });
+    }
+
+    Ok(coverage)
+}
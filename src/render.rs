@@ -1,7 +1,12 @@
-use cgmath::{Matrix4, Ortho, Vector3};
+use cgmath::{Deg, Matrix4, Ortho, Vector2, Vector3, Zero};
 use luminance::{
-    context::GraphicsContext, pipeline::PipelineState, render_state::RenderState, shader::Uniform,
-    tess::Interleaved,
+    blending::{Blending, Equation, Factor},
+    context::GraphicsContext,
+    depth_test::DepthComparison,
+    pipeline::PipelineState,
+    render_state::RenderState,
+    shader::Uniform,
+    tess::{Interleaved, View},
 };
 use luminance_derive::{Semantics, UniformInterface, Vertex};
 use luminance_front::{shader::Program, tess::Tess, Backend};
@@ -11,10 +16,9 @@ use luminance_glyph::{
     VerticalAlign,
 };
 
-use crate::{
-    game::GameState,
-    hexagon::{create_hexagon_mesh_border, flat_hex_height, flat_hex_to_pixel},
-    level::Hex,
+use opencells_rs::{
+    hexagon::hex_corner,
+    scene::{Anchor, DrawHex, DrawRect, DrawText, Scene, FONT},
 };
 
 #[derive(Copy, Clone, Debug, Semantics)]
@@ -23,6 +27,16 @@ pub enum HexVertexSemantics {
     Position,
     #[sem(name = "edginess", repr = "f32", wrapper = "HexVertexEdginess")]
     Edginess,
+    #[sem(name = "center", repr = "[f32; 2]", wrapper = "HexInstanceCenter")]
+    Center,
+    #[sem(name = "stretch", repr = "[f32; 2]", wrapper = "HexInstanceStretch")]
+    Stretch,
+    #[sem(name = "color", repr = "[f32; 3]", wrapper = "HexInstanceColor")]
+    Color,
+    #[sem(name = "hover", repr = "f32", wrapper = "HexInstanceHover")]
+    Hover,
+    #[sem(name = "flags", repr = "u32", wrapper = "HexInstanceFlags")]
+    Flags,
 }
 
 #[derive(Vertex, Clone, Copy)]
@@ -36,230 +50,351 @@ pub struct HexVertex {
     pub(crate) edginess: HexVertexEdginess,
 }
 
+/// Per-cell attributes, drawn in one instanced call.
+#[derive(Vertex, Clone, Copy)]
+#[vertex(sem = "HexVertexSemantics", instanced = "true")]
+pub struct HexInstance {
+    #[allow(dead_code)]
+    pub(crate) center: HexInstanceCenter,
+
+    #[allow(dead_code)]
+    pub(crate) stretch: HexInstanceStretch,
+
+    #[allow(dead_code)]
+    pub(crate) color: HexInstanceColor,
+
+    #[allow(dead_code)]
+    pub(crate) hover: HexInstanceHover,
+
+    #[allow(dead_code)]
+    pub(crate) flags: HexInstanceFlags,
+}
+
+impl HexInstance {
+    fn new(hex: &DrawHex) -> HexInstance {
+        HexInstance {
+            center: HexInstanceCenter::new(hex.center.into()),
+            stretch: HexInstanceStretch::new(hex.stretch.into()),
+            color: HexInstanceColor::new(hex.color.into()),
+            hover: HexInstanceHover::new(hex.hover),
+            flags: HexInstanceFlags::new(hex.flags),
+        }
+    }
+}
+
 #[derive(UniformInterface, Debug)]
 pub struct HexInterface {
     pub(crate) view: Uniform<[[f32; 4]; 4]>,
-    pub(crate) model_color: Uniform<[f32; 3]>,
+    pub(crate) scale: Uniform<f32>,
+    pub(crate) border_color: Uniform<[f32; 3]>,
+    pub(crate) hover_brightness: Uniform<f32>,
 }
 
-const HEX_VS: &'static str = include_str!("shaders/hex-vs.glsl");
-const HEX_FS: &'static str = include_str!("shaders/hex-fs.glsl");
-
+/// Flat colored rectangles, each an instance of the unit square.
 #[derive(Copy, Clone, Debug, Semantics)]
 pub enum BasicVertexSemantics {
-    #[sem(name = "position", repr = "[f32; 2]", wrapper = "BasicVertexPosition")]
+    #[sem(name = "corner", repr = "[f32; 2]", wrapper = "BasicVertexCorner")]
+    Corner,
+    #[sem(
+        name = "position",
+        repr = "[f32; 2]",
+        wrapper = "BasicInstancePosition"
+    )]
     Position,
+    #[sem(name = "size", repr = "[f32; 2]", wrapper = "BasicInstanceSize")]
+    Size,
+    #[sem(name = "color", repr = "[f32; 4]", wrapper = "BasicInstanceColor")]
+    Color,
 }
 
+/// A corner of the unit square, which each instance moves and scales into place.
 #[derive(Vertex, Clone, Copy)]
 #[vertex(sem = "BasicVertexSemantics")]
 pub struct BasicVertex {
     #[allow(dead_code)]
-    pub(crate) position: BasicVertexPosition,
+    pub(crate) corner: BasicVertexCorner,
+}
+
+#[derive(Vertex, Clone, Copy)]
+#[vertex(sem = "BasicVertexSemantics", instanced = "true")]
+pub struct BasicInstance {
+    #[allow(dead_code)]
+    pub(crate) position: BasicInstancePosition,
+
+    #[allow(dead_code)]
+    pub(crate) size: BasicInstanceSize,
+
+    #[allow(dead_code)]
+    pub(crate) color: BasicInstanceColor,
+}
+
+impl BasicInstance {
+    fn new(rect: &DrawRect) -> BasicInstance {
+        BasicInstance {
+            position: BasicInstancePosition::new(rect.position.into()),
+            size: BasicInstanceSize::new(rect.size.into()),
+            color: BasicInstanceColor::new(rect.color),
+        }
+    }
 }
 
 #[derive(UniformInterface, Debug)]
-pub struct BasicVertexInterface {
+pub struct BasicInterface {
     pub(crate) view: Uniform<[[f32; 4]; 4]>,
-    pub(crate) model_color: Uniform<[f32; 3]>,
 }
 
+const HEX_VS: &'static str = include_str!("shaders/hex-vs.glsl");
+const HEX_FS: &'static str = include_str!("shaders/hex-fs.glsl");
 const BASIC_VS: &'static str = include_str!("shaders/basic-2d-vs.glsl");
 const BASIC_FS: &'static str = include_str!("shaders/basic-2d-fs.glsl");
 
 pub type SmallVertexIndex = u16;
 
+type HexTess = Tess<HexVertex, SmallVertexIndex, HexInstance, Interleaved>;
+type RectTess = Tess<BasicVertex, (), BasicInstance, Interleaved>;
+
+/// Instances the hexagon mesh is first created with, grown when a level needs more.
+const INITIAL_INSTANCE_CAPACITY: usize = 256;
+
+/// Creates a bordered hexagon mesh with room for `capacity` instances.
+pub fn create_instanced_hexagon_mesh<C>(context: &mut C, capacity: usize) -> HexTess
+where
+    C: GraphicsContext<Backend = Backend>,
+{
+    let mut verts = Vec::with_capacity(7);
+
+    verts.push(HexVertex {
+        position: HexVertexPosition::new([0.0, 0.0]),
+        edginess: HexVertexEdginess::new(0.0),
+    });
+
+    for i in 0..6 {
+        let outer_position = hex_corner(Vector2::new(0.0, 0.0), 0.95, i);
+        verts.push(HexVertex {
+            position: HexVertexPosition::new(outer_position.into()),
+            edginess: HexVertexEdginess::new(1.0),
+        });
+    }
+
+    #[rustfmt::skip]
+        let indices: &[SmallVertexIndex] = &[
+            0, 1, 2, 3, 4, 5, 6, 1
+        ];
+
+    let empty = DrawHex::new(Vector2::zero(), Vector3::zero(), 0);
+    let instances = vec![HexInstance::new(&empty); capacity.max(1)];
+
+    context
+        .new_tess()
+        .set_vertices(verts)
+        .set_indices(indices)
+        .set_instances(instances)
+        .set_mode(luminance::tess::Mode::TriangleFan)
+        .build()
+        .unwrap()
+}
+
+/// Creates a unit square mesh with room for `capacity` rectangles.
+pub fn create_instanced_rect_mesh<C>(context: &mut C, capacity: usize) -> RectTess
+where
+    C: GraphicsContext<Backend = Backend>,
+{
+    let verts: Vec<BasicVertex> = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+        .iter()
+        .map(|corner| BasicVertex {
+            corner: BasicVertexCorner::new(*corner),
+        })
+        .collect();
+
+    let empty = DrawRect {
+        position: Vector2::zero(),
+        size: Vector2::zero(),
+        color: [0.0; 4],
+    };
+    let instances = vec![BasicInstance::new(&empty); capacity.max(1)];
+
+    context
+        .new_tess()
+        .set_vertices(verts)
+        .set_instances(instances)
+        .set_mode(luminance::tess::Mode::TriangleFan)
+        .build()
+        .unwrap()
+}
+
 pub struct Renderer {
     hex_program: Program<HexVertexSemantics, (), HexInterface>,
-    bordered_hex_mesh: Tess<HexVertex, SmallVertexIndex, (), Interleaved>,
+    hex_mesh: HexTess,
+    /// How many instances `hex_mesh` has room for.
+    instance_capacity: usize,
 
-    basic_program: Program<BasicVertexSemantics, (), BasicVertexInterface>,
+    basic_program: Program<BasicVertexSemantics, (), BasicInterface>,
+    rect_mesh: RectTess,
+    rect_capacity: usize,
 
     glyph_brush: GlyphBrush<Backend>,
-
+    /// Brushes for turned text, one for each angle, since a brush draws everything it holds
+    /// with the same transform.
+    rotated_brushes: Vec<(f32, GlyphBrush<Backend>)>,
+    /// Draws the interface's text, which goes over its rectangles.
+    overlay_brush: GlyphBrush<Backend>,
+
+    /// Physical pixels per logical pixel. Scenes are laid out in logical pixels, so they look the
+    /// same size on every monitor.
+    scale_factor: f32,
+    /// Maps logical pixels to the viewport, for the hexagons.
     projection_matrix: Matrix4<f32>,
+    /// Maps physical pixels to the viewport, for text, which is laid out at its physical size so
+    /// it stays sharp.
+    text_projection_matrix: Matrix4<f32>,
 }
 
 impl Renderer {
-    pub fn new(surface: &mut GlutinSurface) -> Renderer {
+    pub fn new(surface: &mut GlutinSurface, scale_factor: f64) -> Renderer {
         let hex_program = surface
             .new_shader_program::<HexVertexSemantics, (), HexInterface>()
             .from_strings(HEX_VS, None, None, HEX_FS)
             .unwrap()
             .ignore_warnings();
-        let bordered_hex_mesh = create_hexagon_mesh_border(surface);
+        let hex_mesh = create_instanced_hexagon_mesh(surface, INITIAL_INSTANCE_CAPACITY);
 
         let basic_program = surface
-            .new_shader_program::<BasicVertexSemantics, (), BasicVertexInterface>()
+            .new_shader_program::<BasicVertexSemantics, (), BasicInterface>()
             .from_strings(BASIC_VS, None, None, BASIC_FS)
             .unwrap()
             .ignore_warnings();
+        let rect_mesh = create_instanced_rect_mesh(surface, INITIAL_INSTANCE_CAPACITY);
 
-        let glyph_brush = GlyphBrushBuilder::using_font(
-            FontArc::try_from_slice(include_bytes!("../assets/fonts/Aileron-Regular.otf")).unwrap(),
-        )
-        .build(surface);
+        let glyph_brush = Self::create_glyph_brush(surface);
 
         let [width, height] = surface.size();
 
         Renderer {
             hex_program,
-            bordered_hex_mesh,
+            hex_mesh,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
 
             basic_program,
+            rect_mesh,
+            rect_capacity: INITIAL_INSTANCE_CAPACITY,
 
             glyph_brush,
-            projection_matrix: Self::get_projection_matrix(width, height),
+            rotated_brushes: Vec::new(),
+            overlay_brush: Self::create_glyph_brush(surface),
+
+            scale_factor: scale_factor as f32,
+            projection_matrix: Self::get_projection_matrix(
+                width as f32 / scale_factor as f32,
+                height as f32 / scale_factor as f32,
+            ),
+            text_projection_matrix: Self::get_projection_matrix(width as f32, height as f32),
         }
     }
 
-    fn get_projection_matrix(width: u32, height: u32) -> Matrix4<f32> {
+    fn create_glyph_brush(surface: &mut GlutinSurface) -> GlyphBrush<Backend> {
+        GlyphBrushBuilder::using_font(FontArc::try_from_slice(FONT).unwrap()).build(surface)
+    }
+
+    fn get_projection_matrix(width: f32, height: f32) -> Matrix4<f32> {
         Matrix4::from(Ortho {
             left: 0.0,
-            right: width as f32,
-            bottom: height as f32,
+            right: width,
+            bottom: height,
             top: 0.0,
             near: -2.0,
             far: 100.0,
         })
     }
 
-    pub fn update_resolution(&mut self, width: u32, height: u32) {
-        self.projection_matrix = Self::get_projection_matrix(width, height);
+    /// Takes the size of the viewport in physical pixels.
+    pub fn update_resolution(&mut self, width: u32, height: u32, scale_factor: f64) {
+        let scale_factor = scale_factor as f32;
+        let (width, height) = (width as f32, height as f32);
+
+        self.scale_factor = scale_factor;
+        self.projection_matrix =
+            Self::get_projection_matrix(width / scale_factor, height / scale_factor);
+        self.text_projection_matrix = Self::get_projection_matrix(width, height);
     }
 
     pub fn queue_text(&mut self, section: Section) {
         self.glyph_brush.queue(section);
     }
 
-    pub fn render(&mut self, state: &GameState, surface: &mut GlutinSurface) {
-        let level = &state.level;
-        let hex_under_cursor = state.cursor_hex_position;
-        let camera_offset = state.offset;
-        let scale = state.scale;
-
+    pub fn render(&mut self, scene: &Scene, surface: &mut GlutinSurface) {
         let [viewport_width, viewport_height] = surface.size();
 
         let back_buffer = surface.back_buffer().unwrap();
 
-        self.queue_text(
-            Section::default()
-                .add_text(
-                    Text::new("Mistakes: 0")
-                        .with_color([1.0, 1.0, 1.0, 1.0])
-                        .with_scale(48f32)
-                        .with_z(-1.0),
-                )
-                .with_screen_position((viewport_width as f32 - 250.0, 100.0)),
-        );
-
-        self.render_diagonal_hover(state, camera_offset);
-
-        for (pos, cell) in &level.cells {
-            match &cell.hex {
-                Hex::Empty {
-                    show_neighbor_count: true,
-                } => {
-                    self.queue_text(
-                        Section::default()
-                            .add_text(
-                                Text::new(cell.neighbors_str())
-                                    .with_color([1.0, 1.0, 1.0, 1.0])
-                                    .with_scale(state.scale / 2.0)
-                                    .with_z(-1.0),
-                            )
-                            .with_layout(
-                                Layout::default_single_line()
-                                    .h_align(HorizontalAlign::Center)
-                                    .v_align(VerticalAlign::Center),
-                            )
-                            .with_screen_position(
-                                flat_hex_to_pixel(pos.clone(), scale) + camera_offset,
-                            ),
-                    );
-                }
-                _ => {}
+        for text in &scene.texts {
+            if text.rotation == 0.0 {
+                let position = text.position * self.scale_factor;
+                let section = text_section(text, position, self.scale_factor);
+                self.queue_text(section);
+            } else {
+                self.queue_rotated_text(surface, text);
             }
         }
 
+        for text in &scene.overlay_texts {
+            let position = text.position * self.scale_factor;
+            let section = text_section(text, position, self.scale_factor);
+            self.overlay_brush.queue(section);
+        }
+
         self.glyph_brush.process_queued(surface);
+        self.overlay_brush.process_queued(surface);
 
-        let hex_program = &mut self.hex_program;
-        let hex_mesh = &self.bordered_hex_mesh;
+        for (_, brush) in &mut self.rotated_brushes {
+            brush.process_queued(surface);
+        }
+
+        let instances: Vec<HexInstance> = scene.hexes.iter().map(HexInstance::new).collect();
+
+        self.upload_instances(surface, &instances);
 
+        let instance_count = instances.len();
+
+        let rects: Vec<BasicInstance> = scene.rects.iter().map(BasicInstance::new).collect();
+        self.upload_rects(surface, &rects);
+
+        let rect_count = rects.len();
         let basic_program = &mut self.basic_program;
+        let rect_mesh = &self.rect_mesh;
+        let hex_program = &mut self.hex_program;
+        let hex_mesh = &self.hex_mesh;
 
         let glyph_brush = &mut self.glyph_brush;
-        let projection = &self.projection_matrix;
+        let rotated_brushes = &mut self.rotated_brushes;
+        let overlay_brush = &mut self.overlay_brush;
+        let view = self.projection_matrix;
+        let text_view = self.text_projection_matrix;
+        let scale = scene.hex_size;
+        let border_color = scene.border_color;
+        let hover_brightness = scene.hover_brightness;
 
         let render = surface
             .new_pipeline_gate()
             .pipeline(
                 &back_buffer,
-                &PipelineState::default().set_clear_color([0.1, 0.1, 0.1, 1.0]),
+                &PipelineState::default().set_clear_color(scene.clear_color),
                 |mut pipeline, mut shd_gate| {
-                    shd_gate
-                        .shade(hex_program, |mut iface, uni, mut rdr_gate| {
-                            rdr_gate.render(&RenderState::default(), |mut tess_gate| {
-                                for (position, cell) in &level.cells {
-                                    let view = get_hex_view_matrix(
-                                        camera_offset,
-                                        *position,
-                                        scale,
-                                        projection,
-                                    );
-                                    iface.set(&uni.view, view.into());
-
-                                    let color = cell.hex.get_color(true)
-                                        * (if hex_under_cursor == *position {
-                                            1.5
-                                        } else {
-                                            1.0
-                                        });
-
-                                    iface.set(&uni.model_color, color.into());
-
-                                    tess_gate
-                                        .render(hex_mesh)
-                                        .map_err(|_e: &'static str| ())
-                                        .unwrap();
-                                }
-
-                                Ok(())
+                    if instance_count > 0 {
+                        shd_gate
+                            .shade(hex_program, |mut iface, uni, mut rdr_gate| {
+                                iface.set(&uni.view, view.into());
+                                iface.set(&uni.scale, scale);
+                                iface.set(&uni.border_color, border_color.into());
+                                iface.set(&uni.hover_brightness, hover_brightness);
+
+                                rdr_gate.render(&RenderState::default(), |mut tess_gate| {
+                                    let cells = hex_mesh.inst_view(.., instance_count).unwrap();
+                                    tess_gate.render(cells)
+                                })
                             })
-                        })
-                        .map_err(|_: &'static str| ())
-                        .unwrap();
-
-                    shd_gate
-                        .shade(basic_program, |mut iface, uni, mut rdr_gate| {
-                            rdr_gate.render(&RenderState::default(), |mut tess_gate| {
-                                for (&hex_position, cell) in
-                                    level.cells.iter().filter(|cell| cell.1.start_revealed)
-                                {
-                                    if cell.start_revealed {
-                                        let view = get_hex_revealed_indicator_matrix(
-                                            camera_offset,
-                                            hex_position,
-                                            scale,
-                                            projection,
-                                        );
-
-                                        iface.set(&uni.view, view.into());
-                                        iface.set(&uni.model_color, [1.0, 1.0, 1.0]);
-
-                                        tess_gate
-                                            .render(hex_mesh)
-                                            .map_err(|_e: &'static str| ())
-                                            .unwrap();
-                                    }
-                                }
-
-                                Ok(())
-                            })
-                        })
-                        .map_err(|_: &'static str| ())
-                        .unwrap();
+                            .map_err(|_: &'static str| ())
+                            .unwrap();
+                    }
 
                     glyph_brush
                         .draw_queued(
@@ -270,6 +405,47 @@ impl Renderer {
                         )
                         .expect("failed to render glyphs");
 
+                    for (rotation, brush) in rotated_brushes.iter_mut() {
+                        let transform = text_view * Matrix4::from_angle_z(Deg(*rotation));
+                        let transform: &[f32; 16] = transform.as_ref();
+
+                        brush
+                            .draw_queued_with_transform(&mut pipeline, &mut shd_gate, *transform)
+                            .expect("failed to render glyphs");
+                    }
+
+                    // The interface goes over everything, blended so panels can be see-through.
+                    if rect_count > 0 {
+                        let render_state = RenderState::default()
+                            .set_blending(Blending {
+                                equation: Equation::Additive,
+                                src: Factor::SrcAlpha,
+                                dst: Factor::SrcAlphaComplement,
+                            })
+                            .set_depth_test(None::<DepthComparison>);
+
+                        shd_gate
+                            .shade(basic_program, |mut iface, uni, mut rdr_gate| {
+                                iface.set(&uni.view, view.into());
+
+                                rdr_gate.render(&render_state, |mut tess_gate| {
+                                    let rects = rect_mesh.inst_view(.., rect_count).unwrap();
+                                    tess_gate.render(rects)
+                                })
+                            })
+                            .map_err(|_: &'static str| ())
+                            .unwrap();
+                    }
+
+                    overlay_brush
+                        .draw_queued(
+                            &mut pipeline,
+                            &mut shd_gate,
+                            viewport_width,
+                            viewport_height,
+                        )
+                        .expect("failed to render glyphs");
+
                     Ok(())
                 },
             )
@@ -279,52 +455,81 @@ impl Renderer {
         }
     }
 
-    fn render_diagonal_hover(&mut self, state: &GameState, offset: cgmath::Vector2<f32>) {
-        self.queue_text(
-            Section::default()
-                .add_text(
-                    Text::new("=")
-                        .with_color([0.0, 0.0, 1.0, 1.0])
-                        .with_scale(48f32)
-                        .with_z(-1.0),
-                )
-                .with_layout(
-                    Layout::default_single_line()
-                        .h_align(HorizontalAlign::Center)
-                        .v_align(VerticalAlign::Center),
-                )
-                .with_screen_position(state.nearest_edge + offset),
+    /// Queues text on the brush for its angle. The brush turns everything around the top left
+    /// corner, so the text is placed where that turn brings it back to its position, in
+    /// physical pixels.
+    fn queue_rotated_text(&mut self, surface: &mut GlutinSurface, text: &DrawText) {
+        let index = match self
+            .rotated_brushes
+            .iter()
+            .position(|(rotation, _)| *rotation == text.rotation)
+        {
+            Some(index) => index,
+            None => {
+                let brush = Self::create_glyph_brush(surface);
+                self.rotated_brushes.push((text.rotation, brush));
+                self.rotated_brushes.len() - 1
+            }
+        };
+
+        let physical = text.position * self.scale_factor;
+        let (sin, cos) = (-text.rotation).to_radians().sin_cos();
+        let position = Vector2::new(
+            physical.x * cos - physical.y * sin,
+            physical.x * sin + physical.y * cos,
         );
+
+        let section = text_section(text, position, self.scale_factor);
+        self.rotated_brushes[index].1.queue(section);
     }
-}
 
-fn get_hex_revealed_indicator_matrix(
-    camera_offset: cgmath::Vector2<f32>,
-    hex_position: cgmath::Vector2<i32>,
-    scale: f32,
-    projection: &Matrix4<f32>,
-) -> Matrix4<f32> {
-    let offset = camera_offset.extend(0.0);
-    let relative_hex_position = flat_hex_to_pixel(hex_position, scale);
-    let symbol_offset = flat_hex_height(scale) / 2.0 - 16.0;
-    let translation = Matrix4::from_translation(
-        offset + relative_hex_position.extend(0.0) + Vector3::new(0.0, symbol_offset, 1.0),
-    );
-    let scale = Matrix4::from_scale(4.0);
-    let view = projection * translation * scale;
-    view
+    /// Copies instances into the hexagon mesh, recreating it if they don't fit.
+    fn upload_instances(&mut self, surface: &mut GlutinSurface, instances: &[HexInstance]) {
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.hex_mesh = create_instanced_hexagon_mesh(surface, self.instance_capacity);
+        }
+
+        let mut buffer = self
+            .hex_mesh
+            .instances_mut()
+            .expect("failed to map hexagon instances");
+
+        buffer[..instances.len()].copy_from_slice(instances);
+    }
+
+    /// Copies rectangles into the rectangle mesh, recreating it if they don't fit.
+    fn upload_rects(&mut self, surface: &mut GlutinSurface, rects: &[BasicInstance]) {
+        if rects.len() > self.rect_capacity {
+            self.rect_capacity = rects.len().next_power_of_two();
+            self.rect_mesh = create_instanced_rect_mesh(surface, self.rect_capacity);
+        }
+
+        let mut buffer = self
+            .rect_mesh
+            .instances_mut()
+            .expect("failed to map rectangle instances");
+
+        buffer[..rects.len()].copy_from_slice(rects);
+    }
 }
 
-fn get_hex_view_matrix(
-    offset: cgmath::Vector2<f32>,
-    hex_position: cgmath::Vector2<i32>,
-    scale: f32,
-    projection: &Matrix4<f32>,
-) -> Matrix4<f32> {
-    let offset = Vector3::new(offset.x, offset.y, 0.0);
-    let relative_position = flat_hex_to_pixel(hex_position, scale);
-    let translation = Matrix4::from_translation(offset + relative_position.extend(0.0));
-    let scale = Matrix4::from_scale(scale);
-    let view = projection * translation * scale;
-    view
+/// A section for text at `position`, in physical pixels.
+fn text_section(text: &DrawText, position: Vector2<f32>, scale_factor: f32) -> Section {
+    let layout = match text.anchor {
+        Anchor::TopLeft => Layout::default(),
+        Anchor::Center => Layout::default_single_line()
+            .h_align(HorizontalAlign::Center)
+            .v_align(VerticalAlign::Center),
+    };
+
+    Section::default()
+        .add_text(
+            Text::new(&text.text)
+                .with_color(text.color)
+                .with_scale(text.size * scale_factor)
+                .with_z(-1.0),
+        )
+        .with_layout(layout)
+        .with_screen_position((position.x, position.y))
 }
@@ -0,0 +1,127 @@
+use rand::{Error, RngCore};
+
+/// Random number algorithms whose output never changes, so that seeds keep producing the same
+/// levels regardless of platform or the version of `rand` in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngVersion {
+    /// PCG32 (XSH RR) with a fixed stream.
+    V1,
+}
+
+impl RngVersion {
+    pub const LATEST: RngVersion = RngVersion::V1;
+}
+
+pub struct LevelRng {
+    state: u64,
+    increment: u64,
+}
+
+impl LevelRng {
+    pub fn new(version: RngVersion, seed: u64) -> LevelRng {
+        match version {
+            RngVersion::V1 => {
+                let mut rng = LevelRng {
+                    state: 0,
+                    increment: (0xda3e_39cb_94b9_5bdb << 1) | 1,
+                };
+
+                rng.next_pcg32();
+                rng.state = rng.state.wrapping_add(seed);
+                rng.next_pcg32();
+                rng
+            }
+        }
+    }
+
+    fn next_pcg32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(self.increment);
+
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    /// Returns true with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < probability
+    }
+
+    /// Returns a uniformly distributed number below `bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "bound must be positive");
+
+        let bound = bound as u64;
+        let zone = u64::MAX - u64::MAX % bound;
+
+        loop {
+            let value = self.next_u64();
+
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+}
+
+impl RngCore for LevelRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_pcg32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let high = self.next_pcg32() as u64;
+        let low = self.next_pcg32() as u64;
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_pcg32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_matches_reference_pcg32() {
+        // From the reference `pcg32_srandom_r(&rng, 42, 0xda3e39cb94b95bdb)`.
+        let mut rng = LevelRng::new(RngVersion::V1, 42);
+        let values: Vec<u32> = (0..6).map(|_| rng.next_u32()).collect();
+
+        assert_eq!(
+            values,
+            [
+                0x7130_66ea,
+                0x3c7a_0d56,
+                0xf424_216a,
+                0x25c8_9145,
+                0x43e7_ef3e,
+                0x90cf_f60c
+            ]
+        );
+    }
+
+    #[test]
+    fn wide_values_join_two_outputs() {
+        let mut rng = LevelRng::new(RngVersion::V1, 42);
+
+        assert_eq!(rng.next_u64(), 0x7130_66ea_3c7a_0d56);
+
+        let mut bytes = [0; 6];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes, [0x6a, 0x21, 0x24, 0xf4, 0x45, 0x91]);
+    }
+}
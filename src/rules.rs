@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use crate::{
+    hexagon::{axial_neighbors, axial_spiral, Axial},
+    level::{Hex, HintModifier, Level},
+};
+
+/// Marked state per cell, `true` meaning marked.
+pub type Solution = HashMap<Axial, bool>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintSource {
+    /// Count of marked neighbors shown on an empty cell.
+    Neighbors(Axial),
+    /// Count of marked cells within two steps shown on a marked cell.
+    Around(Axial),
+    /// Count of marked cells on a line, keyed by where the hint is displayed.
+    Line(Axial),
+    /// The number of marked cells in the whole level.
+    Total,
+}
+
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub source: HintSource,
+    /// Index of the cell displaying the hint. The hint is only shown while that cell has its
+    /// designed kind, so any other assignment of the host makes the constraint void.
+    pub host: Option<usize>,
+    pub scope: Vec<usize>,
+    pub count: usize,
+    pub arrangement: Option<Arrangement>,
+}
+
+/// The order of the cells a hint with a modifier counts.
+#[derive(Debug, Clone)]
+pub struct Arrangement {
+    pub modifier: HintModifier,
+    /// Cells in order. Neighbor rings have `None` where there's no cell, which splits groups,
+    /// while lines only list their cells.
+    pub slots: Vec<Option<usize>>,
+    /// Whether the last slot is next to the first one.
+    pub cyclic: bool,
+}
+
+impl Arrangement {
+    pub fn is_satisfied(&self, is_marked: impl Fn(usize) -> bool) -> bool {
+        let marked: Vec<bool> = self
+            .slots
+            .iter()
+            .map(|slot| matches!(slot, Some(cell) if is_marked(*cell)))
+            .collect();
+
+        let mut groups = (0..marked.len())
+            .filter(|&i| marked[i] && (i == 0 || !marked[i - 1]))
+            .count();
+
+        if self.cyclic && groups > 1 && marked[0] && marked[marked.len() - 1] {
+            groups -= 1;
+        }
+
+        match self.modifier {
+            HintModifier::Plain => true,
+            HintModifier::Contiguous => groups <= 1,
+            HintModifier::Separated => groups >= 2,
+        }
+    }
+}
+
+/// A level flattened into indexed cells and the constraints its hints impose on them.
+#[derive(Clone)]
+pub struct Puzzle {
+    pub positions: Vec<Axial>,
+    pub indices: HashMap<Axial, usize>,
+    /// The designed marked state of each cell.
+    pub solution: Vec<bool>,
+    /// Whether each cell is revealed when the level starts.
+    pub known: Vec<bool>,
+    pub constraints: Vec<Constraint>,
+    /// Index of the total count in `constraints`.
+    pub total: usize,
+}
+
+impl Puzzle {
+    pub fn from_level(level: &Level) -> Puzzle {
+        let mut positions: Vec<Axial> = level.cells.keys().copied().collect();
+        positions.sort_by_key(|position| (position.x, position.y));
+
+        let indices: HashMap<Axial, usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (*position, index))
+            .collect();
+
+        let solution: Vec<bool> = positions
+            .iter()
+            .map(|position| level.cells[position].hex.is_marked())
+            .collect();
+
+        let known = positions
+            .iter()
+            .map(|position| level.cells[position].start_revealed)
+            .collect();
+
+        let scope_of = |cells: &mut dyn Iterator<Item = Axial>| -> Vec<usize> {
+            cells
+                .filter_map(|cell| indices.get(&cell).copied())
+                .collect()
+        };
+
+        let arrange = |modifier: HintModifier, slots: Vec<Option<usize>>, cyclic: bool| {
+            if modifier == HintModifier::Plain {
+                None
+            } else {
+                Some(Arrangement {
+                    modifier,
+                    slots,
+                    cyclic,
+                })
+            }
+        };
+
+        let mut constraints = Vec::new();
+
+        for (index, position) in positions.iter().enumerate() {
+            let (source, scope, arrangement) = match level.cells[position].hex {
+                Hex::Empty {
+                    show_neighbor_count: true,
+                    modifier,
+                } => {
+                    let slots = axial_neighbors(*position)
+                        .map(|neighbor| indices.get(&neighbor).copied())
+                        .collect();
+
+                    (
+                        HintSource::Neighbors(*position),
+                        scope_of(&mut axial_neighbors(*position)),
+                        arrange(modifier, slots, true),
+                    )
+                }
+                Hex::Marked { show_around: true } => (
+                    HintSource::Around(*position),
+                    scope_of(&mut axial_spiral(*position, 2).into_iter()),
+                    None,
+                ),
+                _ => continue,
+            };
+
+            let count = scope.iter().filter(|&&cell| solution[cell]).count();
+
+            constraints.push(Constraint {
+                source,
+                host: Some(index),
+                scope,
+                count,
+                arrangement,
+            });
+        }
+
+        let mut origins: Vec<&Axial> = level.line_hints.keys().collect();
+        origins.sort_by_key(|origin| (origin.x, origin.y));
+
+        for origin in origins {
+            let hint = &level.line_hints[origin];
+            let scope = scope_of(&mut level.line_cells(*origin, hint.direction).into_iter());
+            let slots = scope.iter().copied().map(Some).collect();
+
+            constraints.push(Constraint {
+                source: HintSource::Line(*origin),
+                host: None,
+                count: scope.iter().filter(|&&cell| solution[cell]).count(),
+                scope,
+                arrangement: arrange(hint.modifier, slots, false),
+            });
+        }
+
+        let total = constraints.len();
+        constraints.push(Constraint {
+            source: HintSource::Total,
+            host: None,
+            scope: (0..positions.len()).collect(),
+            count: solution.iter().filter(|&&marked| marked).count(),
+            arrangement: None,
+        });
+
+        Puzzle {
+            positions,
+            indices,
+            solution,
+            known,
+            constraints,
+            total,
+        }
+    }
+
+    /// Groups the hidden cells into sets that no hint connects, apart from the total count.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut parents: Vec<usize> = (0..self.len()).collect();
+
+        fn find(parents: &mut [usize], cell: usize) -> usize {
+            let mut root = cell;
+
+            while parents[root] != root {
+                root = parents[root];
+            }
+
+            parents[cell] = root;
+            root
+        }
+
+        for constraint in &self.constraints {
+            if constraint.source == HintSource::Total {
+                continue;
+            }
+
+            let mut hidden = constraint
+                .host
+                .iter()
+                .chain(constraint.scope.iter())
+                .copied()
+                .filter(|&cell| !self.known[cell]);
+
+            if let Some(first) = hidden.next() {
+                for cell in hidden {
+                    let (a, b) = (find(&mut parents, first), find(&mut parents, cell));
+                    parents[a] = b;
+                }
+            }
+        }
+
+        let mut components: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for cell in (0..self.len()).filter(|&cell| !self.known[cell]) {
+            let root = find(&mut parents, cell);
+            components.entry(root).or_default().push(cell);
+        }
+
+        let mut components: Vec<Vec<usize>> = components.into_values().collect();
+        components.sort_by_key(|cells| cells[0]);
+        components
+    }
+
+    /// Builds a puzzle over some hidden cells, the revealed cells their hints touch and the
+    /// hints involving them. The total count only covers the given cells.
+    pub fn subpuzzle(&self, cells: &[usize]) -> Puzzle {
+        let mut included = vec![false; self.len()];
+
+        for &cell in cells {
+            included[cell] = true;
+        }
+
+        let constraints: Vec<&Constraint> = self
+            .constraints
+            .iter()
+            .filter(|constraint| constraint.source != HintSource::Total)
+            .filter(|constraint| {
+                constraint
+                    .host
+                    .iter()
+                    .chain(constraint.scope.iter())
+                    .any(|&cell| included[cell])
+            })
+            .collect();
+
+        let mut parent_cells: Vec<usize> = cells.to_vec();
+
+        for constraint in &constraints {
+            for &cell in constraint.host.iter().chain(constraint.scope.iter()) {
+                if !included[cell] {
+                    included[cell] = true;
+                    parent_cells.push(cell);
+                }
+            }
+        }
+
+        let local: HashMap<usize, usize> = parent_cells
+            .iter()
+            .enumerate()
+            .map(|(local, &parent)| (parent, local))
+            .collect();
+
+        let mut constraints: Vec<Constraint> = constraints
+            .into_iter()
+            .map(|constraint| Constraint {
+                source: constraint.source,
+                host: constraint.host.map(|host| local[&host]),
+                scope: constraint.scope.iter().map(|cell| local[cell]).collect(),
+                count: constraint.count,
+                arrangement: constraint
+                    .arrangement
+                    .as_ref()
+                    .map(|arrangement| Arrangement {
+                        modifier: arrangement.modifier,
+                        slots: arrangement
+                            .slots
+                            .iter()
+                            .map(|slot| slot.map(|cell| local[&cell]))
+                            .collect(),
+                        cyclic: arrangement.cyclic,
+                    }),
+            })
+            .collect();
+
+        let total = constraints.len();
+        constraints.push(Constraint {
+            source: HintSource::Total,
+            host: None,
+            scope: (0..cells.len()).collect(),
+            count: cells.iter().filter(|&&cell| self.solution[cell]).count(),
+            arrangement: None,
+        });
+
+        let positions: Vec<Axial> = parent_cells
+            .iter()
+            .map(|&cell| self.positions[cell])
+            .collect();
+
+        Puzzle {
+            indices: positions
+                .iter()
+                .enumerate()
+                .map(|(index, position)| (*position, index))
+                .collect(),
+            positions,
+            solution: parent_cells
+                .iter()
+                .map(|&cell| self.solution[cell])
+                .collect(),
+            known: parent_cells.iter().map(|&cell| self.known[cell]).collect(),
+            constraints,
+            total,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn to_solution(&self, values: &[bool]) -> Solution {
+        self.positions
+            .iter()
+            .copied()
+            .zip(values.iter().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_levels::level;
+
+    #[test]
+    fn hints_become_constraints() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked number
+             cell 0 1 empty
+             cell 3 0 empty
+             line 0 -1 down contiguous",
+        ));
+
+        let positions = [(0, 0), (0, 1), (1, 0), (3, 0)];
+        assert_eq!(
+            puzzle.positions,
+            positions
+                .iter()
+                .map(|&(q, r)| Axial::new(q, r))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(puzzle.solution, [false, false, true, false]);
+        assert_eq!(puzzle.known, [true, false, false, false]);
+
+        let summary: Vec<_> = puzzle
+            .constraints
+            .iter()
+            .map(|constraint| {
+                let mut scope = constraint.scope.clone();
+                scope.sort_unstable();
+                (constraint.source, constraint.host, scope, constraint.count)
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (
+                    HintSource::Neighbors(Axial::new(0, 0)),
+                    Some(0),
+                    vec![1, 2],
+                    1
+                ),
+                (
+                    HintSource::Around(Axial::new(1, 0)),
+                    Some(2),
+                    vec![0, 1, 3],
+                    0
+                ),
+                (HintSource::Line(Axial::new(0, -1)), None, vec![0, 1], 0),
+                (HintSource::Total, None, vec![0, 1, 2, 3], 1),
+            ]
+        );
+        assert_eq!(puzzle.total, 3);
+
+        let line = puzzle.constraints[2].arrangement.as_ref().unwrap();
+        assert_eq!(line.modifier, HintModifier::Contiguous);
+        assert_eq!(line.slots, [Some(0), Some(1)]);
+        assert!(!line.cyclic);
+        assert!(puzzle.constraints[0].arrangement.is_none());
+    }
+
+    #[test]
+    fn arrangements_count_groups() {
+        let ring = |modifier| Arrangement {
+            modifier,
+            slots: vec![Some(0), Some(1), None, Some(2), Some(3), Some(4)],
+            cyclic: true,
+        };
+        let marked = |cells: &'static [usize]| move |cell| cells.contains(&cell);
+
+        let contiguous = ring(HintModifier::Contiguous);
+        assert!(contiguous.is_satisfied(marked(&[0, 1])));
+        // The first and last slots are next to each other around a cell.
+        assert!(contiguous.is_satisfied(marked(&[4, 0, 1])));
+        // A missing cell splits the ring.
+        assert!(!contiguous.is_satisfied(marked(&[1, 2])));
+
+        let separated = ring(HintModifier::Separated);
+        assert!(separated.is_satisfied(marked(&[1, 2])));
+        assert!(!separated.is_satisfied(marked(&[4, 0])));
+
+        let line = Arrangement {
+            cyclic: false,
+            ..ring(HintModifier::Separated)
+        };
+        assert!(line.is_satisfied(marked(&[4, 0])));
+    }
+
+    #[test]
+    fn components_split_unconnected_hidden_cells() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty
+             cell 3 0 empty",
+        ));
+
+        assert_eq!(puzzle.components(), [vec![1, 2], vec![3]]);
+
+        let subpuzzle = puzzle.subpuzzle(&[1, 2]);
+        assert_eq!(
+            subpuzzle.positions,
+            [Axial::new(0, 1), Axial::new(1, 0), Axial::new(0, 0)]
+        );
+        assert_eq!(subpuzzle.known, [false, false, true]);
+        assert_eq!(subpuzzle.solution, [false, true, false]);
+
+        // The revealed count keeps its host, and the total only covers the given cells.
+        let neighbors = &subpuzzle.constraints[0];
+        assert_eq!(neighbors.host, Some(2));
+        assert_eq!(neighbors.count, 1);
+
+        let total = &subpuzzle.constraints[subpuzzle.total];
+        assert_eq!(total.source, HintSource::Total);
+        assert_eq!(total.scope, [0, 1]);
+        assert_eq!(total.count, 1);
+    }
+}
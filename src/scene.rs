@@ -0,0 +1,270 @@
+//! Describes a frame as plain data, so what gets drawn can be inspected without a GPU. The
+//! windowed renderer turns a `Scene` into draw calls and `raster` draws it into an image.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use cgmath::{Vector2, Vector3};
+
+use crate::{
+    hexagon::{flat_hex_height, flat_hex_to_pixel},
+    hints::HintCache,
+    level::{Hex, Level, LineDirection},
+    theme::Theme,
+    view::{draw_board, Board, Reveal},
+};
+
+// Keep in sync with the flags in hex-fs.glsl.
+pub const SELECTED: u32 = 2;
+pub const START_REVEALED: u32 = 4;
+/// Stripes on hidden cells and a ring on marked ones, for themes that don't rely on color alone.
+pub const HIDDEN_PATTERN: u32 = 8;
+pub const MARKED_PATTERN: u32 = 16;
+
+/// The font all text is drawn with.
+pub const FONT: &[u8] = include_bytes!("../assets/fonts/Aileron-Regular.otf");
+
+/// A hexagon, with its center in screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawHex {
+    pub center: Vector2<f32>,
+    /// How much the hexagon is stretched horizontally and vertically.
+    pub stretch: Vector2<f32>,
+    pub color: Vector3<f32>,
+    /// How hovered the hexagon looks, from 0 to 1.
+    pub hover: f32,
+    /// `SELECTED`, `START_REVEALED` and the patterns, combined.
+    pub flags: u32,
+}
+
+impl DrawHex {
+    pub fn new(center: Vector2<f32>, color: Vector3<f32>, flags: u32) -> DrawHex {
+        DrawHex {
+            center,
+            stretch: Vector2::new(1.0, 1.0),
+            color,
+            hover: 0.0,
+            flags,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Center,
+}
+
+/// A line of text, positioned in screen pixels by its anchor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawText {
+    pub text: String,
+    pub position: Vector2<f32>,
+    pub size: f32,
+    pub color: [f32; 4],
+    pub anchor: Anchor,
+    /// Degrees the text is turned clockwise around its position.
+    pub rotation: f32,
+}
+
+/// A filled rectangle, with its top left corner and size in screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawRect {
+    pub position: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub color: [f32; 4],
+}
+
+/// Everything drawn in a frame: hexagons first and their text on top, then the interface's
+/// rectangles and its own text over the level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    /// Width and height of the frame, in pixels.
+    pub size: Vector2<f32>,
+    pub clear_color: [f32; 4],
+    pub border_color: Vector3<f32>,
+    /// How much brighter hovered hexagons are.
+    pub hover_brightness: f32,
+    /// Distance from a hexagon's center to its corners, in pixels.
+    pub hex_size: f32,
+    pub hexes: Vec<DrawHex>,
+    pub texts: Vec<DrawText>,
+    pub rects: Vec<DrawRect>,
+    pub overlay_texts: Vec<DrawText>,
+}
+
+impl Scene {
+    pub fn new(size: Vector2<f32>, hex_size: f32, theme: &Theme) -> Scene {
+        Scene {
+            size,
+            clear_color: theme.background_color(),
+            border_color: theme.border,
+            hover_brightness: theme.hover,
+            hex_size,
+            hexes: Vec::new(),
+            texts: Vec::new(),
+            rects: Vec::new(),
+            overlay_texts: Vec::new(),
+        }
+    }
+
+    pub fn text(
+        &mut self,
+        text: impl Into<String>,
+        position: Vector2<f32>,
+        size: f32,
+        color: [f32; 4],
+    ) {
+        self.texts.push(DrawText {
+            text: text.into(),
+            position,
+            size,
+            color,
+            anchor: Anchor::TopLeft,
+            rotation: 0.0,
+        });
+    }
+
+    pub fn centered_text(
+        &mut self,
+        text: impl Into<String>,
+        position: Vector2<f32>,
+        size: f32,
+        color: [f32; 4],
+    ) {
+        self.texts.push(DrawText {
+            text: text.into(),
+            position,
+            size,
+            color,
+            anchor: Anchor::Center,
+            rotation: 0.0,
+        });
+    }
+
+    /// Centered text turned clockwise by `rotation` degrees.
+    pub fn rotated_text(
+        &mut self,
+        text: impl Into<String>,
+        position: Vector2<f32>,
+        size: f32,
+        color: [f32; 4],
+        rotation: f32,
+    ) {
+        self.texts.push(DrawText {
+            text: text.into(),
+            position,
+            size,
+            color,
+            anchor: Anchor::Center,
+            rotation,
+        });
+    }
+}
+
+/// How wide a line of text is when drawn at `size`, in pixels.
+pub fn text_width(text: &str, size: f32) -> f32 {
+    let font = FontRef::try_from_slice(FONT).expect("the bundled font is valid");
+    let font = font.as_scaled(PxScale::from(size));
+
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+
+    width
+}
+
+/// Degrees to turn a line hint so it's read across the line it counts, given the line's step
+/// in pixels. A hint for a column is upright; others are turned by at most a quarter turn
+/// either way to stay readable.
+pub fn line_hint_rotation(step: Vector2<f32>) -> f32 {
+    let mut rotation = step.y.atan2(step.x).to_degrees().round() - 90.0;
+
+    if rotation <= -180.0 {
+        rotation += 360.0;
+    }
+
+    if rotation > 90.0 {
+        rotation -= 180.0;
+    } else if rotation <= -90.0 {
+        rotation += 180.0;
+    }
+
+    rotation
+}
+
+/// The rotation of a line hint in the game's flat-top layout.
+pub fn flat_line_hint_rotation(direction: LineDirection) -> f32 {
+    line_hint_rotation(flat_hex_to_pixel(direction.step(), 1.0))
+}
+
+/// The pattern flags for a cell, if the theme uses patterns.
+pub fn pattern_flags(theme: &Theme, hex: Hex, revealed: bool) -> u32 {
+    match hex {
+        _ if !theme.patterns => 0,
+        _ if !revealed => HIDDEN_PATTERN,
+        Hex::Marked { .. } => MARKED_PATTERN,
+        Hex::Empty { .. } => 0,
+    }
+}
+
+/// The top left corner and size of the area a level's cells and line hints cover when drawn at
+/// `scale`, with room for the hexagons around the outermost centers.
+pub fn level_bounds(level: &Level, scale: f32) -> (Vector2<f32>, Vector2<f32>) {
+    let centers: Vec<Vector2<f32>> = level
+        .cells
+        .keys()
+        .chain(level.line_hints.keys())
+        .map(|position| flat_hex_to_pixel(*position, scale))
+        .collect();
+
+    let (min, max) = centers.iter().fold(
+        (
+            Vector2::new(f32::MAX, f32::MAX),
+            Vector2::new(f32::MIN, f32::MIN),
+        ),
+        |(min, max), center| {
+            (
+                Vector2::new(min.x.min(center.x), min.y.min(center.y)),
+                Vector2::new(max.x.max(center.x), max.y.max(center.y)),
+            )
+        },
+    );
+
+    let (min, max) = if centers.is_empty() {
+        (Vector2::new(0.0, 0.0), Vector2::new(0.0, 0.0))
+    } else {
+        (min, max)
+    };
+
+    let margin = Vector2::new(scale, flat_hex_height(scale) / 2.0);
+    (min - margin, max - min + margin * 2.0)
+}
+
+/// Draws a whole level as it looks when it starts, or solved with `show_solution`, framed
+/// tightly around its cells. Used for screenshots and thumbnails outside the game.
+pub fn level_scene(level: &Level, scale: f32, show_solution: bool, theme: &Theme) -> Scene {
+    let (origin, size) = level_bounds(level, scale);
+    let size = Vector2::new(size.x.ceil(), size.y.ceil());
+
+    let hints = HintCache::new(level);
+    let reveal = if show_solution {
+        Reveal::Solution
+    } else {
+        Reveal::Start
+    };
+    let board = Board {
+        offset: -origin,
+        ..Board::new(level, &hints, theme, scale, reveal)
+    };
+
+    let mut scene = Scene::new(size, scale, theme);
+    draw_board(&mut scene, &board);
+    scene
+}
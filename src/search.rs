@@ -0,0 +1,361 @@
+use crate::rules::Puzzle;
+
+/// Backtracking search over the unknown cells of a puzzle, with unit propagation on the
+/// constraints. Every assignment is recorded on a trail so it can be rolled back.
+pub struct Search<'a> {
+    puzzle: &'a Puzzle,
+    values: Vec<Option<bool>>,
+
+    marked: Vec<usize>,
+    unassigned: Vec<usize>,
+
+    in_scope: Vec<Vec<usize>>,
+    hosted: Vec<Vec<usize>>,
+
+    trail: Vec<usize>,
+    pending: Vec<usize>,
+    order: Vec<usize>,
+
+    contradicted: bool,
+    /// Checked at every branch. Once it returns true, the search stops as if `visit` had.
+    cancelled: Option<&'a dyn Fn() -> bool>,
+}
+
+impl<'a> Search<'a> {
+    /// Creates a search with every start-revealed cell assigned. If this already contradicts the
+    /// constraints, the search has no solutions.
+    pub fn new(puzzle: &'a Puzzle) -> Search<'a> {
+        let cell_count = puzzle.len();
+
+        let mut in_scope = vec![Vec::new(); cell_count];
+        let mut hosted = vec![Vec::new(); cell_count];
+
+        for (index, constraint) in puzzle.constraints.iter().enumerate() {
+            for &cell in &constraint.scope {
+                in_scope[cell].push(index);
+            }
+
+            if let Some(host) = constraint.host {
+                hosted[host].push(index);
+            }
+        }
+
+        let mut order = Vec::with_capacity(cell_count);
+        let mut ordered = vec![false; cell_count];
+
+        let mut local: Vec<usize> = (0..puzzle.constraints.len())
+            .filter(|&index| puzzle.constraints[index].host.is_some())
+            .collect();
+        local.sort_by_key(|&index| puzzle.constraints[index].scope.len());
+
+        for index in local {
+            let constraint = &puzzle.constraints[index];
+
+            for cell in constraint.host.iter().chain(constraint.scope.iter()) {
+                if !ordered[*cell] {
+                    ordered[*cell] = true;
+                    order.push(*cell);
+                }
+            }
+        }
+
+        order.extend((0..cell_count).filter(|&cell| !ordered[cell]));
+
+        let mut search = Search {
+            puzzle,
+            values: vec![None; cell_count],
+            marked: vec![0; puzzle.constraints.len()],
+            unassigned: puzzle
+                .constraints
+                .iter()
+                .map(|constraint| constraint.scope.len())
+                .collect(),
+            in_scope,
+            hosted,
+            trail: Vec::new(),
+            pending: Vec::new(),
+            order,
+            contradicted: false,
+            cancelled: None,
+        };
+
+        for cell in 0..cell_count {
+            if puzzle.known[cell]
+                && search.values[cell].is_none()
+                && !search.assign(cell, puzzle.solution[cell])
+            {
+                search.contradicted = true;
+                break;
+            }
+        }
+
+        search
+    }
+
+    /// Creates a search that gives up once `cancelled` returns true.
+    pub fn cancellable(puzzle: &'a Puzzle, cancelled: &'a dyn Fn() -> bool) -> Search<'a> {
+        let mut search = Search::new(puzzle);
+        search.cancelled = Some(cancelled);
+        search
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.trail.len()
+    }
+
+    pub fn rollback(&mut self, checkpoint: usize) {
+        while self.trail.len() > checkpoint {
+            let cell = self.trail.pop().unwrap();
+
+            if self.values[cell] == Some(true) {
+                for &constraint in &self.in_scope[cell] {
+                    self.marked[constraint] -= 1;
+                }
+            }
+
+            for &constraint in &self.in_scope[cell] {
+                self.unassigned[constraint] += 1;
+            }
+
+            self.values[cell] = None;
+        }
+
+        self.pending.clear();
+    }
+
+    /// Assigns a value to a cell and propagates its consequences. Returns false if this leads to
+    /// a contradiction, in which case the caller should roll back.
+    pub fn assign(&mut self, cell: usize, value: bool) -> bool {
+        self.set(cell, value);
+        self.propagate()
+    }
+
+    fn set(&mut self, cell: usize, value: bool) {
+        debug_assert!(self.values[cell].is_none());
+
+        self.values[cell] = Some(value);
+        self.trail.push(cell);
+
+        for &constraint in &self.in_scope[cell] {
+            self.unassigned[constraint] -= 1;
+
+            if value {
+                self.marked[constraint] += 1;
+            }
+
+            self.pending.push(constraint);
+        }
+
+        self.pending.extend(self.hosted[cell].iter().copied());
+    }
+
+    fn propagate(&mut self) -> bool {
+        while let Some(index) = self.pending.pop() {
+            let constraint = &self.puzzle.constraints[index];
+            let marked = self.marked[index];
+            let unassigned = self.unassigned[index];
+
+            let mut satisfiable =
+                marked <= constraint.count && marked + unassigned >= constraint.count;
+
+            if satisfiable && unassigned == 0 {
+                if let Some(arrangement) = &constraint.arrangement {
+                    let values = &self.values;
+                    satisfiable = arrangement.is_satisfied(|cell| values[cell] == Some(true));
+                }
+            }
+
+            match constraint.host.map(|host| (host, self.values[host])) {
+                Some((host, None)) => {
+                    // An unsatisfiable hint can't be displayed, so its host must be the other kind.
+                    if !satisfiable {
+                        self.set(host, !self.puzzle.solution[host]);
+                    }
+                    continue;
+                }
+                Some((host, Some(value))) if value != self.puzzle.solution[host] => continue,
+                _ => {}
+            }
+
+            if !satisfiable {
+                self.pending.clear();
+                return false;
+            }
+
+            if unassigned == 0 {
+                continue;
+            }
+
+            let forced = if marked == constraint.count {
+                false
+            } else if marked + unassigned == constraint.count {
+                true
+            } else {
+                continue;
+            };
+
+            for i in 0..constraint.scope.len() {
+                let cell = self.puzzle.constraints[index].scope[i];
+
+                if self.values[cell].is_none() {
+                    self.set(cell, forced);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Calls `visit` for every complete assignment consistent with the constraints, trying the
+    /// designed value of each cell first. Stops early when `visit` returns true or the search is
+    /// cancelled.
+    pub fn for_each_solution(&mut self, visit: &mut impl FnMut(&[bool]) -> bool) -> bool {
+        if self.contradicted {
+            return false;
+        }
+
+        if self.cancelled.is_some_and(|cancelled| cancelled()) {
+            return true;
+        }
+
+        let next = self
+            .order
+            .iter()
+            .copied()
+            .find(|&cell| self.values[cell].is_none());
+
+        let cell = match next {
+            Some(cell) => cell,
+            None => {
+                let values: Vec<bool> = self.values.iter().map(|value| value.unwrap()).collect();
+                return visit(&values);
+            }
+        };
+
+        let designed = self.puzzle.solution[cell];
+
+        for &value in &[designed, !designed] {
+            let checkpoint = self.checkpoint();
+
+            if self.assign(cell, value) && self.for_each_solution(visit) {
+                self.rollback(checkpoint);
+                return true;
+            }
+
+            self.rollback(checkpoint);
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_levels::level;
+
+    fn solutions(puzzle: &Puzzle) -> Vec<Vec<bool>> {
+        let mut solutions = Vec::new();
+
+        Search::new(puzzle).for_each_solution(&mut |values| {
+            solutions.push(values.to_vec());
+            false
+        });
+
+        solutions
+    }
+
+    #[test]
+    fn revealed_counts_propagate() {
+        // The line sees no marked cells, so the only other neighbor of the count must be marked.
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty
+             line 0 -1 down",
+        ));
+        let search = Search::new(&puzzle);
+
+        assert_eq!(search.values, [Some(false), Some(false), Some(true)]);
+        assert_eq!(solutions(&puzzle), vec![puzzle.solution.clone()]);
+    }
+
+    #[test]
+    fn unsatisfiable_hint_makes_its_host_the_other_kind() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number
+             cell 1 0 marked
+             cell 5 5 empty",
+        ));
+        let mut search = Search::new(&puzzle);
+        let checkpoint = search.checkpoint();
+
+        // Without its only marked neighbor, the count can't be shown, so its cell is marked and
+        // the total rules out the last cell.
+        assert!(search.assign(1, false));
+        assert_eq!(search.values, [Some(true), Some(false), Some(false)]);
+
+        search.rollback(checkpoint);
+        assert_eq!(search.values, [None, None, None]);
+        assert_eq!(search.marked, vec![0; puzzle.constraints.len()]);
+        assert_eq!(search.unassigned, [1, 3]);
+    }
+
+    #[test]
+    fn hints_of_cells_of_the_other_kind_are_void() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number
+             cell 1 0 marked",
+        ));
+
+        // Marking the numbered cell hides its count, which frees its neighbor.
+        assert_eq!(solutions(&puzzle), [vec![false, true], vec![true, false]]);
+    }
+
+    #[test]
+    fn contradicting_revealed_cells_have_no_solutions() {
+        let mut puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+        puzzle.constraints[0].count = 3;
+
+        assert!(Search::new(&puzzle).contradicted);
+        assert!(solutions(&puzzle).is_empty());
+    }
+
+    #[test]
+    fn designed_solution_comes_first() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+
+        assert_eq!(
+            solutions(&puzzle),
+            [vec![false, false, true], vec![false, true, false]]
+        );
+    }
+
+    #[test]
+    fn cancelled_search_stops() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+        let cancelled = || true;
+
+        let mut visited = false;
+        let stopped = Search::cancellable(&puzzle, &cancelled).for_each_solution(&mut |_| {
+            visited = true;
+            false
+        });
+
+        assert!(stopped);
+        assert!(!visited);
+        assert_eq!(solutions(&puzzle).len(), 3);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use cgmath::Zero;
+
+use crate::{
+    hexagon::Axial,
+    level::{CellState, Hex, HintModifier, Level, LineHint},
+};
+
+/// How newly selected positions combine with the current selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectMode {
+    Replace,
+    Add,
+    Remove,
+}
+
+/// Positions of selected cells and line hints.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    positions: HashSet<Axial>,
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn contains(&self, position: Axial) -> bool {
+        self.positions.contains(&position)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.positions.iter().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+    }
+
+    pub fn select(&mut self, positions: impl IntoIterator<Item = Axial>, mode: SelectMode) {
+        match mode {
+            SelectMode::Replace => {
+                self.positions.clear();
+                self.positions.extend(positions);
+            }
+            SelectMode::Add => self.positions.extend(positions),
+            SelectMode::Remove => {
+                for position in positions {
+                    self.positions.remove(&position);
+                }
+            }
+        }
+    }
+
+    /// Forgets positions that no longer have a cell or a line hint on them.
+    pub fn retain_existing(&mut self, level: &Level) {
+        self.positions.retain(|position| {
+            level.cells.contains_key(position) || level.line_hints.contains_key(position)
+        });
+    }
+
+    fn sorted(&self) -> Vec<Axial> {
+        let mut positions: Vec<Axial> = self.iter().collect();
+        positions.sort_by_key(|position| (position.x, position.y));
+        positions
+    }
+}
+
+/// Doubled coordinates, where rows count half cells so that columns line up on screen.
+fn doubled(position: Axial) -> (i32, i32) {
+    (position.x, 2 * position.y + position.x)
+}
+
+/// Cells and line hints inside the rectangle spanned by two corners, as it looks on screen.
+pub fn rectangle(level: &Level, from: Axial, to: Axial) -> Vec<Axial> {
+    let (from_column, from_row) = doubled(from);
+    let (to_column, to_row) = doubled(to);
+
+    let columns = from_column.min(to_column)..=from_column.max(to_column);
+    let rows = from_row.min(to_row)..=from_row.max(to_row);
+
+    level
+        .cells
+        .keys()
+        .chain(level.line_hints.keys())
+        .copied()
+        .filter(|position| {
+            let (column, row) = doubled(*position);
+            columns.contains(&column) && rows.contains(&row)
+        })
+        .collect()
+}
+
+/// Cells and line hints copied out of a level, relative to the first copied position.
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    /// Copied cells and whether they are revealed at the start.
+    cells: Vec<(Axial, Hex, bool)>,
+    line_hints: Vec<(Axial, LineHint)>,
+}
+
+impl Clipboard {
+    /// Copies the selected cells and line hints, or returns `None` if nothing is selected.
+    pub fn copy(level: &Level, selection: &Selection) -> Option<Clipboard> {
+        let positions = selection.sorted();
+        let anchor = *positions.first()?;
+
+        let cells = positions
+            .iter()
+            .filter_map(|position| {
+                let cell = level.cells.get(position)?;
+                Some((position - anchor, cell.hex, cell.start_revealed))
+            })
+            .collect();
+
+        let line_hints = positions
+            .iter()
+            .filter_map(|position| {
+                let hint = level.line_hints.get(position)?;
+                Some((position - anchor, *hint))
+            })
+            .collect();
+
+        Some(Clipboard { cells, line_hints })
+    }
+
+    /// Places the copied cells and line hints with the first one at `position`, replacing
+    /// whatever is there. Returns the pasted positions.
+    pub fn paste(&self, level: &mut Level, position: Axial) -> Vec<Axial> {
+        let mut pasted = Vec::new();
+
+        for (offset, hex, start_revealed) in &self.cells {
+            let mut cell = CellState::new(*hex);
+            cell.start_revealed = *start_revealed;
+
+            level.line_hints.remove(&(position + offset));
+            level.cells.insert(position + offset, cell);
+            pasted.push(position + offset);
+        }
+
+        for (offset, hint) in &self.line_hints {
+            level.cells.remove(&(position + offset));
+            level.line_hints.insert(position + offset, *hint);
+            pasted.push(position + offset);
+        }
+
+        pasted
+    }
+}
+
+/// Removes the selected cells and line hints.
+pub fn delete(level: &mut Level, selection: &Selection) {
+    for position in selection.iter() {
+        level.cells.remove(&position);
+        level.line_hints.remove(&position);
+    }
+}
+
+/// Moves the selected cells and line hints by `offset`, replacing whatever they land on, and
+/// moves the selection along with them.
+pub fn move_selection(level: &mut Level, selection: &mut Selection, offset: Axial) {
+    if offset.is_zero() {
+        return;
+    }
+
+    let positions = selection.sorted();
+
+    let cells: Vec<(Axial, CellState)> = positions
+        .iter()
+        .filter_map(|position| Some((*position, level.cells.remove(position)?)))
+        .collect();
+
+    let line_hints: Vec<(Axial, LineHint)> = positions
+        .iter()
+        .filter_map(|position| Some((*position, level.line_hints.remove(position)?)))
+        .collect();
+
+    for (position, cell) in cells {
+        level.line_hints.remove(&(position + offset));
+        level.cells.insert(position + offset, cell);
+    }
+
+    for (position, hint) in line_hints {
+        level.cells.remove(&(position + offset));
+        level.line_hints.insert(position + offset, hint);
+    }
+
+    selection.select(
+        positions.into_iter().map(|position| position + offset),
+        SelectMode::Replace,
+    );
+}
+
+/// Sets whether the selected cells are revealed at the start.
+pub fn set_start_revealed(level: &mut Level, selection: &Selection, start_revealed: bool) {
+    for position in selection.iter() {
+        if let Some(cell) = level.cells.get_mut(&position) {
+            cell.start_revealed = start_revealed;
+        }
+    }
+}
+
+/// Turns the selected cells into empty or marked cells. Cells that already are of that kind
+/// keep their hints.
+pub fn set_marked(level: &mut Level, selection: &Selection, marked: bool) {
+    for position in selection.iter() {
+        let cell = match level.cells.get_mut(&position) {
+            Some(cell) if cell.hex.is_marked() != marked => cell,
+            _ => continue,
+        };
+
+        cell.hex = if marked {
+            Hex::Marked { show_around: false }
+        } else {
+            Hex::Empty {
+                show_neighbor_count: true,
+                modifier: HintModifier::Plain,
+            }
+        };
+    }
+}
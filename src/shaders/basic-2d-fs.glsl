@@ -1,7 +1,7 @@
-out vec4 frag;
+in vec4 v_color;
 
-uniform vec3 model_color;
+out vec4 frag;
 
 void main() {
-  frag = vec4(model_color, 1.0);
+  frag = v_color;
 }
\ No newline at end of file
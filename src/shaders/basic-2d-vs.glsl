@@ -1,7 +1,14 @@
-in vec2 co;
+in vec2 corner;
+
+in vec2 position;
+in vec2 size;
+in vec4 color;
+
+out vec4 v_color;
 
 uniform mat4 view;
 
 void main() {
-  gl_Position = view * vec4(co, 0., 1.);
+  gl_Position = view * vec4(position + corner * size, 0., 1.);
+  v_color = color;
 }
\ No newline at end of file
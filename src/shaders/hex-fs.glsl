@@ -1,22 +1,72 @@
 in float v_edginess;
+in vec2 v_offset;
+in vec3 v_color;
+in float v_hover;
+flat in uint v_flags;
 
 out vec4 frag;
 
-uniform vec3 model_color;
+uniform float scale;
+uniform vec3 border_color;
+uniform float hover_brightness;
 
-const vec4 border_color = vec4(1.0, 1.0, 1.0, 1.0);
+// Keep in sync with the flags in scene.rs.
+const uint SELECTED = 2u;
+const uint START_REVEALED = 4u;
+const uint HIDDEN_PATTERN = 8u;
+const uint MARKED_PATTERN = 16u;
+
+const float INDICATOR_RADIUS = 3.8;
+// Distance between stripes on hidden cells, relative to the scale.
+const float STRIPE_PERIOD = 0.3;
+// Where the ring on marked cells starts and ends, in edginess.
+const float RING_INNER = 0.45;
+const float RING_OUTER = 0.55;
+
+// Whether a point is inside a flat-top hexagon of the given radius around the origin.
+bool in_hexagon(vec2 point, float radius) {
+  vec2 p = abs(point);
+  float half_height = 0.8660254 * radius;
+  return p.y <= half_height && 0.8660254 * p.x + 0.5 * p.y <= half_height;
+}
 
 void main() {
-  vec4 hex_color = vec4(model_color, 1.0);
+  vec3 color = v_color;
+
+  color *= mix(1.0, hover_brightness, v_hover);
+
+  float period = STRIPE_PERIOD * scale;
+  if ((v_flags & HIDDEN_PATTERN) != 0u && mod(v_offset.x + v_offset.y, period) < period / 3.0) {
+    color *= 0.7;
+  }
+
+  if ((v_flags & MARKED_PATTERN) != 0u && v_edginess > RING_INNER && v_edginess < RING_OUTER) {
+    color = border_color;
+  }
+
+  if ((v_flags & SELECTED) != 0u) {
+    color = color * 0.5 + vec3(0.5);
+  }
+
+  // Cells revealed at the start get a small hexagon near their top edge.
+  vec2 indicator_center = vec2(0.0, 0.8660254 * scale - 16.0);
+
+  if ((v_flags & START_REVEALED) != 0u && in_hexagon(v_offset - indicator_center, INDICATOR_RADIUS)) {
+    frag = vec4(1.0);
+    return;
+  }
+
+  vec4 hex_color = vec4(color, 1.0);
+  vec4 border = vec4(border_color, 1.0);
 
   if (v_edginess > 0.95) {
-    frag = mix(hex_color, border_color, 0.95);
+    frag = mix(hex_color, border, 0.95);
   }
   else if (v_edginess < 0.8) {
     frag = hex_color;
   }
   else {
     float clamped_edginess = clamp(pow(v_edginess, 10.0), 0.0, 1.0);
-    frag = mix(hex_color, border_color, clamped_edginess);
+    frag = mix(hex_color, border, clamped_edginess);
   }
 }
\ No newline at end of file
@@ -1,10 +1,28 @@
-in vec2 co;
+in vec2 position;
 in float edginess;
 
+in vec2 center;
+in vec2 stretch;
+in vec3 color;
+in float hover;
+in uint flags;
+
 out float v_edginess;
+out vec2 v_offset;
+out vec3 v_color;
+out float v_hover;
+flat out uint v_flags;
+
 uniform mat4 view;
+uniform float scale;
 
 void main() {
-  gl_Position = view * vec4(co, 0., 1.);
+  vec2 offset = position * stretch * scale;
+
+  gl_Position = view * vec4(center + offset, 0., 1.);
   v_edginess = edginess;
+  v_offset = offset;
+  v_color = color;
+  v_hover = hover;
+  v_flags = flags;
 }
\ No newline at end of file
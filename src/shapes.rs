@@ -0,0 +1,316 @@
+use std::{fmt, fs};
+
+use crate::hexagon::{axial_spiral, Axial};
+
+/// Masks are read one pixel per cell, so anything much larger than a level is a mistake. This also
+/// keeps the pixel count from overflowing.
+const MAX_MASK_PIXELS: usize = 4096 * 4096;
+
+/// Board templates, as cell positions relative to the origin.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Hexagon {
+        radius: u32,
+    },
+    /// Triangle pointing right, with `size` cells per side.
+    Triangle {
+        size: u32,
+    },
+    /// Parallelogram along the axial axes.
+    Rhombus {
+        width: u32,
+        height: u32,
+    },
+    /// Columns of `height` cells, zigzagging to keep the top and bottom edges level.
+    Rectangle {
+        width: u32,
+        height: u32,
+    },
+    Mask(Mask),
+}
+
+impl Shape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Hexagon { .. } => "hexagon",
+            Shape::Triangle { .. } => "triangle",
+            Shape::Rhombus { .. } => "rhombus",
+            Shape::Rectangle { .. } => "rectangle",
+            Shape::Mask(_) => "mask",
+        }
+    }
+
+    pub fn cells(&self) -> Vec<Axial> {
+        match self {
+            Shape::Hexagon { radius } => {
+                let mut cells = vec![Axial::new(0, 0)];
+                cells.extend(axial_spiral(Axial::new(0, 0), *radius));
+                cells
+            }
+            Shape::Triangle { size } => {
+                let size = *size as i32;
+
+                (0..size)
+                    .flat_map(|q| (0..size - q).map(move |r| Axial::new(q, r)))
+                    .collect()
+            }
+            Shape::Rhombus { width, height } => (0..*width as i32)
+                .flat_map(|q| (0..*height as i32).map(move |r| Axial::new(q, r)))
+                .collect(),
+            Shape::Rectangle { width, height } => (0..*width as i32)
+                .flat_map(|column| (0..*height as i32).map(move |row| offset_to_axial(column, row)))
+                .collect(),
+            Shape::Mask(mask) => mask.cells.clone(),
+        }
+    }
+}
+
+/// Converts column and row coordinates, with every odd column shifted down by half a cell, to
+/// axial coordinates.
+pub fn offset_to_axial(column: i32, row: i32) -> Axial {
+    Axial::new(column, row - column.div_euclid(2))
+}
+
+#[derive(Debug)]
+pub enum MaskError {
+    UnsupportedFormat,
+    Malformed,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaskError::UnsupportedFormat => write!(f, "not a PBM or PGM image"),
+            MaskError::Malformed => write!(f, "malformed image"),
+        }
+    }
+}
+
+/// An arbitrary set of cells read from a bitmap, one pixel or character per cell in the column
+/// and row layout of `offset_to_axial`.
+#[derive(Debug, Clone)]
+pub struct Mask {
+    pub cells: Vec<Axial>,
+}
+
+impl Mask {
+    /// Reads a mask from an image, or from text unless the file is named as an image.
+    pub fn load(path: &str) -> Result<Mask, String> {
+        let bytes = fs::read(path).map_err(|error| format!("couldn't read {}: {}", path, error))?;
+
+        match Mask::from_netpbm(&bytes) {
+            Ok(mask) => Ok(mask),
+            Err(_) if !path.ends_with(".pbm") && !path.ends_with(".pgm") => {
+                Ok(Mask::from_ascii(&String::from_utf8_lossy(&bytes)))
+            }
+            Err(error) => Err(format!("{}: {}", path, error)),
+        }
+    }
+
+    /// Reads a mask from text, where spaces and `.` are holes and any other character is a cell.
+    pub fn from_ascii(text: &str) -> Mask {
+        let mut cells = Vec::new();
+
+        for (row, line) in text.lines().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                if character != ' ' && character != '.' {
+                    cells.push(offset_to_axial(column as i32, row as i32));
+                }
+            }
+        }
+
+        Mask { cells }
+    }
+
+    /// Reads a mask from a PBM or PGM image, where dark pixels are cells.
+    pub fn from_netpbm(bytes: &[u8]) -> Result<Mask, MaskError> {
+        let mut reader = NetpbmReader { bytes, position: 0 };
+
+        let magic = reader.token().ok_or(MaskError::Malformed)?;
+        let width = reader.number()?;
+        let height = reader.number()?;
+        let count = pixel_count(width, height)?;
+
+        let pixels: Vec<bool> = match magic {
+            b"P1" => (0..count).map(|_| reader.bit()).collect::<Result<_, _>>()?,
+            b"P4" => {
+                let data = reader.binary_data()?;
+                let row_bytes = width.div_ceil(8);
+
+                if data.len() < row_bytes * height {
+                    return Err(MaskError::Malformed);
+                }
+
+                (0..count)
+                    .map(|i| {
+                        let (y, x) = (i / width, i % width);
+                        data[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+                    })
+                    .collect()
+            }
+            b"P2" => {
+                let max = reader.number()?;
+
+                (0..count)
+                    .map(|_| reader.number().map(|value| value.saturating_mul(2) < max))
+                    .collect::<Result<_, _>>()?
+            }
+            b"P5" => {
+                let max = reader.number()?;
+                let data = reader.binary_data()?;
+
+                if max > 255 || data.len() < count {
+                    return Err(MaskError::Malformed);
+                }
+
+                data[..count]
+                    .iter()
+                    .map(|&value| (value as usize) * 2 < max)
+                    .collect()
+            }
+            _ => return Err(MaskError::UnsupportedFormat),
+        };
+
+        let cells = (0..count)
+            .filter(|&i| pixels[i])
+            .map(|i| offset_to_axial((i % width) as i32, (i / width) as i32))
+            .collect();
+
+        Ok(Mask { cells })
+    }
+}
+
+fn pixel_count(width: usize, height: usize) -> Result<usize, MaskError> {
+    width
+        .checked_mul(height)
+        .filter(|&count| count <= MAX_MASK_PIXELS)
+        .ok_or(MaskError::Malformed)
+}
+
+struct NetpbmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> NetpbmReader<'a> {
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.position) {
+            if byte == b'#' {
+                while matches!(self.bytes.get(self.position), Some(&byte) if byte != b'\n') {
+                    self.position += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace_and_comments();
+
+        let start = self.position;
+
+        while matches!(self.bytes.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+
+        if start == self.position {
+            None
+        } else {
+            Some(&self.bytes[start..self.position])
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, MaskError> {
+        self.token()
+            .and_then(|token| std::str::from_utf8(token).ok())
+            .and_then(|token| token.parse().ok())
+            .ok_or(MaskError::Malformed)
+    }
+
+    /// Plain PBM pixels may be written without whitespace between them.
+    fn bit(&mut self) -> Result<bool, MaskError> {
+        self.skip_whitespace_and_comments();
+
+        let bit = match self.bytes.get(self.position) {
+            Some(b'1') => true,
+            Some(b'0') => false,
+            _ => return Err(MaskError::Malformed),
+        };
+
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// Raster data follows the header after a single whitespace character.
+    fn binary_data(&mut self) -> Result<&'a [u8], MaskError> {
+        match self.bytes.get(self.position) {
+            Some(byte) if byte.is_ascii_whitespace() => Ok(&self.bytes[self.position + 1..]),
+            _ => Err(MaskError::Malformed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Vec<Axial>) -> Vec<Axial> {
+        cells.sort_by_key(|cell| (cell.x, cell.y));
+        cells
+    }
+
+    #[test]
+    fn formats_read_the_same_cells() {
+        // Each odd column is shifted down by half a cell.
+        let expected = sorted(vec![
+            offset_to_axial(0, 0),
+            offset_to_axial(1, 0),
+            offset_to_axial(1, 1),
+        ]);
+
+        let ascii = Mask::from_ascii("xx\n.x\n");
+        let plain_pbm = Mask::from_netpbm(b"P1\n# comment\n2 2\n11\n01\n").unwrap();
+        let raw_pbm = Mask::from_netpbm(b"P4 2 2\n\xc0\x40").unwrap();
+        let plain_pgm = Mask::from_netpbm(b"P2 2 2 255\n0 0\n255 10\n").unwrap();
+        let raw_pgm = Mask::from_netpbm(b"P5 2 2 255\n\x00\x00\xff\x0a").unwrap();
+
+        for mask in [ascii, plain_pbm, raw_pbm, plain_pgm, raw_pgm] {
+            assert_eq!(sorted(mask.cells), expected);
+        }
+    }
+
+    #[test]
+    fn malformed_images_are_rejected() {
+        let cases: [&[u8]; 6] = [
+            b"",
+            b"P1 2",
+            b"P1 2 2 1 0 1",
+            b"P4 2 2\n\xc0",
+            b"P5 2 2 65535\n\x00\x00\x00\x00",
+            b"P4 18446744073709551615 2\n",
+        ];
+
+        for bytes in cases.iter() {
+            assert!(matches!(
+                Mask::from_netpbm(bytes),
+                Err(MaskError::Malformed)
+            ));
+        }
+
+        assert!(matches!(
+            Mask::from_netpbm(b"P3 1 1 255 0 0 0"),
+            Err(MaskError::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn huge_images_are_rejected() {
+        assert!(matches!(
+            Mask::from_netpbm(b"P1 4097 4096\n"),
+            Err(MaskError::Malformed)
+        ));
+    }
+}
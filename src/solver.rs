@@ -0,0 +1,559 @@
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use crate::{
+    hexagon::Axial,
+    level::Level,
+    rules::{HintSource, Puzzle},
+};
+
+/// Ways of deducing a cell, from easiest to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// A single hint is either already satisfied or needs every hidden cell it covers.
+    Single,
+    /// Two overlapping hints bound how many marked cells their overlap can hold.
+    Pair,
+    /// Assuming the opposite state leads to a contradiction within the visible hints.
+    Contradiction,
+}
+
+impl Technique {
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::Single => "single hints",
+            Technique::Pair => "pairs of hints",
+            Technique::Contradiction => "contradictions",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Deduction {
+    pub cell: Axial,
+    pub marked: bool,
+    /// The hints that had to be combined to reach this deduction.
+    pub hints: Vec<HintSource>,
+}
+
+/// One round of play: every deduction available with the easiest technique that makes progress.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub technique: Technique,
+    pub deductions: Vec<Deduction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    /// Cells still hidden when the solver ran out of deductions.
+    pub stuck: Vec<Axial>,
+}
+
+impl Trace {
+    pub fn is_solved(&self) -> bool {
+        self.stuck.is_empty()
+    }
+}
+
+/// Solves a level the way a player would, only using hints on revealed cells and the total
+/// number of marked cells.
+pub fn solve(level: &Level) -> Trace {
+    let puzzle = Puzzle::from_level(level);
+    solve_puzzle(&puzzle)
+}
+
+pub fn solve_puzzle(puzzle: &Puzzle) -> Trace {
+    let mut solver = Solver::new(puzzle);
+    let mut steps = Vec::new();
+
+    while let Some(step) = solver.next_step() {
+        for deduction in &step.deductions {
+            solver.reveal(puzzle.indices[&deduction.cell]);
+        }
+
+        steps.push(step);
+    }
+
+    let stuck = (0..puzzle.len())
+        .filter(|&cell| solver.values[cell].is_none())
+        .map(|cell| puzzle.positions[cell])
+        .collect();
+
+    Trace { steps, stuck }
+}
+
+/// Hints with a modifier and more hidden cells than this are only used for counting.
+const MAX_ARRANGED_CELLS: usize = 16;
+
+/// Sets of cells that are all marked or all empty.
+type CellGroups = Vec<(Vec<usize>, bool)>;
+
+struct Solver<'a> {
+    puzzle: &'a Puzzle,
+    values: Vec<Option<bool>>,
+    visible: Vec<bool>,
+    cell_constraints: Vec<Vec<usize>>,
+    hosted: Vec<Vec<usize>>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(puzzle: &'a Puzzle) -> Solver<'a> {
+        let mut cell_constraints = vec![Vec::new(); puzzle.len()];
+        let mut hosted = vec![Vec::new(); puzzle.len()];
+
+        for (index, constraint) in puzzle.constraints.iter().enumerate() {
+            for &cell in &constraint.scope {
+                cell_constraints[cell].push(index);
+            }
+
+            if let Some(host) = constraint.host {
+                hosted[host].push(index);
+            }
+        }
+
+        let mut solver = Solver {
+            puzzle,
+            values: vec![None; puzzle.len()],
+            visible: puzzle
+                .constraints
+                .iter()
+                .map(|constraint| constraint.host.is_none())
+                .collect(),
+            cell_constraints,
+            hosted,
+        };
+
+        for cell in 0..puzzle.len() {
+            if puzzle.known[cell] {
+                solver.reveal(cell);
+            }
+        }
+
+        solver
+    }
+
+    fn reveal(&mut self, cell: usize) {
+        self.values[cell] = Some(self.puzzle.solution[cell]);
+
+        for &index in &self.hosted[cell] {
+            self.visible[index] = true;
+        }
+    }
+
+    fn visible_constraints(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.puzzle.constraints.len()).filter(move |&index| self.visible[index])
+    }
+
+    /// Marked cells still missing from a constraint, and its hidden cells.
+    fn remaining(&self, values: &[Option<bool>], index: usize) -> (isize, Vec<usize>) {
+        let constraint = &self.puzzle.constraints[index];
+        let mut remaining = constraint.count as isize;
+        let mut hidden = Vec::new();
+
+        for &cell in &constraint.scope {
+            match values[cell] {
+                Some(true) => remaining -= 1,
+                Some(false) => {}
+                None => hidden.push(cell),
+            }
+        }
+
+        (remaining, hidden)
+    }
+
+    fn next_step(&self) -> Option<Step> {
+        let techniques = [Technique::Single, Technique::Pair, Technique::Contradiction];
+
+        techniques.iter().find_map(|&technique| {
+            let deductions = match technique {
+                Technique::Single => self.single_deductions(),
+                Technique::Pair => self.pair_deductions(),
+                Technique::Contradiction => self.contradiction_deductions(),
+            };
+
+            if deductions.is_empty() {
+                None
+            } else {
+                Some(Step {
+                    technique,
+                    deductions,
+                })
+            }
+        })
+    }
+
+    fn single_deductions(&self) -> Vec<Deduction> {
+        let mut deductions = Deductions::default();
+
+        for index in self.visible_constraints() {
+            let (remaining, hidden) = self.remaining(&self.values, index);
+            let hints = [self.puzzle.constraints[index].source];
+
+            if remaining == 0 {
+                deductions.extend(self.puzzle, &hidden, false, &hints);
+            } else if remaining == hidden.len() as isize {
+                deductions.extend(self.puzzle, &hidden, true, &hints);
+            } else {
+                for (cells, marked) in self.arranged_deductions(index, remaining, &hidden) {
+                    deductions.extend(self.puzzle, &cells, marked, &hints);
+                }
+            }
+        }
+
+        deductions.list
+    }
+
+    /// Tries every way of placing the remaining marked cells of a hint with a modifier, and
+    /// returns the cells that have the same state in all of the valid ones.
+    fn arranged_deductions(&self, index: usize, remaining: isize, hidden: &[usize]) -> CellGroups {
+        let arrangement = match &self.puzzle.constraints[index].arrangement {
+            Some(arrangement) if hidden.len() <= MAX_ARRANGED_CELLS => arrangement,
+            _ => return Vec::new(),
+        };
+
+        let all = (1u32 << hidden.len()) - 1;
+        let mut always_marked = all;
+        let mut ever_marked = 0;
+
+        for mask in (0..=all).filter(|mask| mask.count_ones() as isize == remaining) {
+            let is_marked = |cell| match hidden.iter().position(|&hidden| hidden == cell) {
+                Some(bit) => mask & (1 << bit) != 0,
+                None => self.values[cell] == Some(true),
+            };
+
+            if arrangement.is_satisfied(is_marked) {
+                always_marked &= mask;
+                ever_marked |= mask;
+            }
+        }
+
+        if ever_marked == 0 {
+            return Vec::new();
+        }
+
+        let select = |mask: u32| -> Vec<usize> {
+            (0..hidden.len())
+                .filter(|bit| mask & (1 << bit) != 0)
+                .map(|bit| hidden[bit])
+                .collect()
+        };
+
+        vec![
+            (select(always_marked), true),
+            (select(all & !ever_marked), false),
+        ]
+    }
+
+    fn pair_deductions(&self) -> Vec<Deduction> {
+        let mut seen = HashSet::new();
+        let mut pairs = Vec::new();
+
+        for cell in (0..self.puzzle.len()).filter(|&cell| self.values[cell].is_none()) {
+            let constraints: Vec<usize> = self.cell_constraints[cell]
+                .iter()
+                .copied()
+                .filter(|&index| self.visible[index])
+                .collect();
+
+            for (i, &first) in constraints.iter().enumerate() {
+                for &second in &constraints[i + 1..] {
+                    if seen.insert((first, second)) {
+                        pairs.push((first, second));
+                    }
+                }
+            }
+        }
+
+        let found: Vec<(usize, usize, CellGroups)> = pairs
+            .into_par_iter()
+            .map(|(first, second)| (first, second, self.pair_deduction(first, second)))
+            .collect();
+
+        let mut deductions = Deductions::default();
+
+        for (first, second, groups) in found {
+            let hints = [
+                self.puzzle.constraints[first].source,
+                self.puzzle.constraints[second].source,
+            ];
+
+            for (cells, marked) in groups {
+                deductions.extend(self.puzzle, &cells, marked, &hints);
+            }
+        }
+
+        deductions.list
+    }
+
+    /// Bounds how many marked cells fit in the overlap of two hints, and returns groups of
+    /// cells that these bounds decide.
+    fn pair_deduction(&self, first: usize, second: usize) -> CellGroups {
+        let (first_remaining, first_hidden) = self.remaining(&self.values, first);
+        let (second_remaining, second_hidden) = self.remaining(&self.values, second);
+
+        let (overlap, first_only): (Vec<usize>, Vec<usize>) = first_hidden
+            .iter()
+            .partition(|cell| second_hidden.contains(cell));
+        let second_only: Vec<usize> = second_hidden
+            .iter()
+            .copied()
+            .filter(|cell| !overlap.contains(cell))
+            .collect();
+
+        let overlap_len = overlap.len() as isize;
+        let min_overlap = 0
+            .max(first_remaining - first_only.len() as isize)
+            .max(second_remaining - second_only.len() as isize);
+        let max_overlap = overlap_len.min(first_remaining).min(second_remaining);
+
+        let mut groups = Vec::new();
+
+        for (remaining, only) in [
+            (first_remaining, first_only),
+            (second_remaining, second_only),
+        ] {
+            if only.is_empty() {
+                continue;
+            }
+
+            if remaining - max_overlap == only.len() as isize {
+                groups.push((only, true));
+            } else if remaining - min_overlap == 0 {
+                groups.push((only, false));
+            }
+        }
+
+        if min_overlap == overlap_len {
+            groups.push((overlap, true));
+        } else if max_overlap == 0 {
+            groups.push((overlap, false));
+        }
+
+        groups
+    }
+
+    fn contradiction_deductions(&self) -> Vec<Deduction> {
+        let frontier: HashSet<usize> = self
+            .visible_constraints()
+            .flat_map(|index| self.remaining(&self.values, index).1)
+            .collect();
+
+        let mut frontier: Vec<usize> = frontier.into_iter().collect();
+        frontier.sort_unstable();
+
+        let refuted: Vec<(usize, bool, Vec<HintSource>)> = frontier
+            .into_par_iter()
+            .filter_map(|cell| {
+                [true, false].iter().find_map(|&assumed| {
+                    self.refute(cell, assumed)
+                        .map(|hints| (cell, !assumed, hints))
+                })
+            })
+            .collect();
+
+        let mut deductions = Deductions::default();
+
+        for (cell, marked, hints) in refuted {
+            deductions.extend(self.puzzle, &[cell], marked, &hints);
+        }
+
+        deductions.list
+    }
+
+    fn is_arranged(&self, index: usize, values: &[Option<bool>]) -> bool {
+        match &self.puzzle.constraints[index].arrangement {
+            Some(arrangement) => arrangement.is_satisfied(|cell| values[cell] == Some(true)),
+            None => true,
+        }
+    }
+
+    /// Propagates single-hint deductions from an assumption. Returns the hints involved if this
+    /// runs into a contradiction.
+    fn refute(&self, cell: usize, assumed: bool) -> Option<Vec<HintSource>> {
+        let mut values = self.values.clone();
+        values[cell] = Some(assumed);
+
+        let mut used = Vec::new();
+        let mut pending = self.cell_constraints[cell].clone();
+
+        while let Some(index) = pending.pop() {
+            if !self.visible[index] {
+                continue;
+            }
+
+            let (remaining, hidden) = self.remaining(&values, index);
+
+            let contradicted = remaining < 0
+                || remaining > hidden.len() as isize
+                || (hidden.is_empty() && !self.is_arranged(index, &values));
+
+            if contradicted {
+                if !used.contains(&index) {
+                    used.push(index);
+                }
+
+                return Some(
+                    used.iter()
+                        .map(|&index| self.puzzle.constraints[index].source)
+                        .collect(),
+                );
+            }
+
+            let marked = if hidden.is_empty() {
+                continue;
+            } else if remaining == 0 {
+                false
+            } else if remaining == hidden.len() as isize {
+                true
+            } else {
+                continue;
+            };
+
+            if !used.contains(&index) {
+                used.push(index);
+            }
+
+            for cell in hidden {
+                values[cell] = Some(marked);
+                pending.extend(self.cell_constraints[cell].iter().copied());
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Default)]
+struct Deductions {
+    list: Vec<Deduction>,
+    seen: HashSet<usize>,
+}
+
+impl Deductions {
+    fn extend(&mut self, puzzle: &Puzzle, cells: &[usize], marked: bool, hints: &[HintSource]) {
+        for &cell in cells {
+            if self.seen.insert(cell) {
+                debug_assert_eq!(
+                    puzzle.solution[cell], marked,
+                    "Deduction contradicts the level."
+                );
+
+                self.list.push(Deduction {
+                    cell: puzzle.positions[cell],
+                    marked,
+                    hints: hints.to_vec(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_levels::level;
+
+    /// Both neighbors of the count are needed to reach it.
+    pub const SINGLE: &str = "
+        cell 0 0 empty number revealed
+        cell 1 0 marked
+        cell 0 1 marked";
+
+    /// The count covers the line's two cells and one more. The line holds the count's only
+    /// marked cell, so the third cell is empty, which no single hint shows.
+    pub const PAIR: &str = "
+        cell 0 0 empty number revealed
+        cell 1 -1 marked
+        cell 1 0 empty
+        cell 0 1 empty number
+        line 1 -2 down";
+
+    /// Marking the cell both counts share would use them both up, leaving too few marked cells
+    /// for the total. No pair of hints shows this.
+    pub const CONTRADICTION: &str = "
+        cell 0 1 empty number revealed
+        cell 2 -1 empty number revealed
+        cell 1 0 empty number
+        cell 1 1 empty
+        cell 2 0 empty
+        cell -1 2 marked
+        cell 2 -2 marked";
+
+    fn deduced(step: &Step) -> Vec<(Axial, bool)> {
+        let mut cells: Vec<_> = step
+            .deductions
+            .iter()
+            .map(|deduction| (deduction.cell, deduction.marked))
+            .collect();
+        cells.sort_by_key(|(cell, _)| (cell.x, cell.y));
+        cells
+    }
+
+    #[test]
+    fn single_hint_fills_its_cells() {
+        let trace = solve(&level(SINGLE));
+
+        assert!(trace.is_solved());
+        assert_eq!(trace.steps.len(), 1);
+
+        let step = &trace.steps[0];
+        assert_eq!(step.technique, Technique::Single);
+        assert_eq!(
+            deduced(step),
+            [(Axial::new(0, 1), true), (Axial::new(1, 0), true)]
+        );
+        assert!(step
+            .deductions
+            .iter()
+            .all(|deduction| deduction.hints == [HintSource::Neighbors(Axial::new(0, 0))]));
+    }
+
+    #[test]
+    fn pair_of_hints_bounds_their_overlap() {
+        let trace = solve(&level(PAIR));
+
+        assert!(trace.is_solved());
+
+        let step = &trace.steps[0];
+        assert_eq!(step.technique, Technique::Pair);
+        assert_eq!(deduced(step), [(Axial::new(0, 1), false)]);
+        assert_eq!(step.deductions[0].hints.len(), 2);
+        assert!(step.deductions[0]
+            .hints
+            .contains(&HintSource::Neighbors(Axial::new(0, 0))));
+
+        // The revealed cell's count of zero finishes the level.
+        assert!(trace.steps[1..]
+            .iter()
+            .all(|step| step.technique == Technique::Single));
+    }
+
+    #[test]
+    fn contradiction_rules_out_an_assumption() {
+        let trace = solve(&level(CONTRADICTION));
+
+        assert!(trace.is_solved());
+
+        let step = &trace.steps[0];
+        assert_eq!(step.technique, Technique::Contradiction);
+        assert_eq!(deduced(step), [(Axial::new(1, 0), false)]);
+
+        let hints = &step.deductions[0].hints;
+        assert!(hints.contains(&HintSource::Total));
+        assert!(hints.len() >= 2);
+    }
+
+    #[test]
+    fn ambiguous_level_gets_stuck() {
+        let trace = solve(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+
+        assert!(trace.steps.is_empty());
+        assert_eq!(trace.stuck, [Axial::new(0, 1), Axial::new(1, 0)]);
+    }
+}
@@ -0,0 +1,41 @@
+//! A line at the bottom of the window reporting what the last action did, shown for a few
+//! seconds.
+
+use std::time::{Duration, Instant};
+
+/// How long a message stays up.
+const DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Default)]
+pub struct Status {
+    message: Option<(String, Instant)>,
+}
+
+impl Status {
+    /// Shows `message`, replacing the one shown before.
+    pub fn show(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    /// When the message shown goes away.
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.message
+            .as_ref()
+            .map(|(_, shown_at)| *shown_at + DURATION)
+    }
+
+    /// Takes the message down once its time is up, returning whether it did.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.expires_at() {
+            Some(expires_at) if now >= expires_at => {
+                self.message = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
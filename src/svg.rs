@@ -0,0 +1,284 @@
+use std::fmt::Write;
+
+use cgmath::{Vector2, Vector3};
+
+use crate::{
+    hexagon::{flat_hex_to_pixel, hex_corner},
+    level::{Hex, Level},
+    scene::{level_bounds, line_hint_rotation, pattern_flags, HIDDEN_PATTERN, MARKED_PATTERN},
+    theme::Theme,
+};
+
+/// Which way the hexagons point. The game uses flat tops; pointy tops turn the whole level a
+/// quarter turn clockwise, keeping the text upright.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    FlatTop,
+    PointyTop,
+}
+
+impl Orientation {
+    pub fn from_name(name: &str) -> Option<Orientation> {
+        match name {
+            "flat" => Some(Orientation::FlatTop),
+            "pointy" => Some(Orientation::PointyTop),
+            _ => None,
+        }
+    }
+
+    /// Moves a point from the flat-top layout to this orientation.
+    fn place(self, point: Vector2<f32>) -> Vector2<f32> {
+        match self {
+            Orientation::FlatTop => point,
+            Orientation::PointyTop => Vector2::new(-point.y, point.x),
+        }
+    }
+}
+
+/// Colors and lines the level is drawn with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgStyle {
+    pub theme: Theme,
+    /// Width of the line around each cell, relative to the cell size.
+    pub border_width: f32,
+    pub font_family: String,
+}
+
+impl SvgStyle {
+    pub fn from_theme(theme: Theme) -> SvgStyle {
+        SvgStyle {
+            theme,
+            border_width: 0.06,
+            font_family: "sans-serif".to_string(),
+        }
+    }
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle::from_theme(Theme::default())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Distance from a cell's center to its corners, in pixels.
+    pub scale: f32,
+    /// Draws every cell revealed instead of how the level starts.
+    pub show_solution: bool,
+    pub orientation: Orientation,
+    pub style: SvgStyle,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            scale: 32.0,
+            show_solution: false,
+            orientation: Orientation::FlatTop,
+            style: SvgStyle::default(),
+        }
+    }
+}
+
+/// Stripes on hidden cells and the ring on marked cells, as drawn in the game.
+const STRIPE_PERIOD: f32 = 0.3;
+const RING_RADIUS: f32 = 0.5;
+const RING_WIDTH: f32 = 0.1 * 0.866;
+
+fn to_hex_color(color: Vector3<f32>) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.x),
+        channel(color.y),
+        channel(color.z)
+    )
+}
+
+/// Escapes text for use in element content and quoted attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_svg(level: &Level, options: &SvgOptions) -> String {
+    let scale = options.scale;
+    let style = &options.style;
+    let theme = &style.theme;
+    let orientation = options.orientation;
+
+    let (origin, size) = level_bounds(level, scale);
+
+    // Turning the level turns its bounds with it.
+    let (origin, size) = match orientation {
+        Orientation::FlatTop => (origin, size),
+        Orientation::PointyTop => (
+            Vector2::new(-(origin.y + size.y), origin.x),
+            Vector2::new(size.y, size.x),
+        ),
+    };
+
+    let center = |position| orientation.place(flat_hex_to_pixel(position, scale));
+
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        origin.x,
+        origin.y,
+        size.x,
+        size.y,
+        size.x.ceil(),
+        size.y.ceil()
+    )
+    .unwrap();
+
+    if !level.info.title.is_empty() {
+        writeln!(svg, "<title>{}</title>", escape(&level.info.title)).unwrap();
+    }
+
+    writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        origin.x,
+        origin.y,
+        size.x,
+        size.y,
+        to_hex_color(theme.background)
+    )
+    .unwrap();
+
+    // Text is centered on its position and turned by `rotation` degrees around it.
+    let text = |svg: &mut String, center: Vector2<f32>, rotation: f32, label: &str| {
+        let transform = if rotation == 0.0 {
+            String::new()
+        } else {
+            format!(
+                r#" transform="rotate({} {} {})""#,
+                rotation, center.x, center.y
+            )
+        };
+
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}"{} font-family="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            center.x,
+            center.y,
+            transform,
+            escape(&style.font_family),
+            scale / 2.0,
+            to_hex_color(theme.text),
+            escape(label)
+        )
+        .unwrap();
+    };
+
+    let points = |position, radius: f32| {
+        let points: Vec<String> = (0..6)
+            .map(|i| hex_corner(flat_hex_to_pixel(position, scale), radius, i))
+            .map(|corner| orientation.place(corner))
+            .map(|corner| format!("{},{}", corner.x, corner.y))
+            .collect();
+
+        points.join(" ")
+    };
+
+    if theme.patterns {
+        // The same stripes as the game's, darkening a third of the cell.
+        let period = STRIPE_PERIOD * scale / 2f32.sqrt();
+
+        writeln!(
+            svg,
+            r#"<defs><pattern id="hidden-pattern" width="{0}" height="{0}" patternUnits="userSpaceOnUse" patternTransform="rotate(45)"><rect width="{1}" height="{0}" fill="black" fill-opacity="0.3"/></pattern></defs>"#,
+            period,
+            period / 3.0
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        svg,
+        r#"<g stroke="{}" stroke-width="{}" stroke-linejoin="round">"#,
+        to_hex_color(theme.border),
+        style.border_width * scale
+    )
+    .unwrap();
+
+    let positions = level.sorted_positions();
+
+    for position in &positions {
+        let cell = &level.cells[position];
+        let revealed = options.show_solution || cell.start_revealed;
+
+        writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{}"/>"#,
+            points(*position, scale * 0.95),
+            to_hex_color(theme.cell_color(cell.hex, revealed))
+        )
+        .unwrap();
+
+        match pattern_flags(theme, cell.hex, revealed) {
+            HIDDEN_PATTERN => writeln!(
+                svg,
+                r#"<polygon points="{}" fill="url(#hidden-pattern)" stroke="none"/>"#,
+                points(*position, scale * 0.95)
+            )
+            .unwrap(),
+            MARKED_PATTERN => writeln!(
+                svg,
+                r#"<polygon points="{}" fill="none" stroke-width="{}"/>"#,
+                points(*position, scale * 0.95 * RING_RADIUS),
+                scale * 0.95 * RING_WIDTH
+            )
+            .unwrap(),
+            _ => {}
+        }
+    }
+
+    svg.push_str("</g>\n");
+
+    for position in &positions {
+        let cell = &level.cells[position];
+
+        if !options.show_solution && !cell.start_revealed {
+            continue;
+        }
+
+        let label = match cell.hex {
+            Hex::Empty {
+                show_neighbor_count: true,
+                modifier,
+            } => modifier.decorate(level.neighbor_count(*position)),
+            Hex::Marked { show_around: true } => level.around_count(*position).to_string(),
+            _ => continue,
+        };
+
+        text(&mut svg, center(*position), 0.0, &label);
+    }
+
+    let mut origins: Vec<_> = level.line_hints.keys().copied().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    for origin in origins {
+        let hint = level.line_hints[&origin];
+        let count = level.line_count(origin, hint.direction);
+
+        let step = orientation.place(flat_hex_to_pixel(hint.direction.step(), 1.0));
+        let rotation = line_hint_rotation(step);
+
+        text(
+            &mut svg,
+            center(origin),
+            rotation,
+            &hint.modifier.decorate(count),
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,99 @@
+use crate::hexagon::{axial_to_cube, cube_to_axial, Axial, Cube};
+
+/// Ways edits can be repeated across a level, around a center cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    Off,
+    /// Mirrored across the vertical line through the center.
+    MirrorLeftRight,
+    /// Mirrored across the horizontal line through the center.
+    MirrorTopBottom,
+    /// Rotated around the center, repeating 2, 3 or 6 times in a full turn.
+    Rotate(u32),
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::Off,
+        Symmetry::MirrorLeftRight,
+        Symmetry::MirrorTopBottom,
+        Symmetry::Rotate(2),
+        Symmetry::Rotate(3),
+        Symmetry::Rotate(6),
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Symmetry::Off => "off".to_string(),
+            Symmetry::MirrorLeftRight => "mirrored left to right".to_string(),
+            Symmetry::MirrorTopBottom => "mirrored top to bottom".to_string(),
+            Symmetry::Rotate(folds) => format!("{}-fold rotation", folds),
+        }
+    }
+
+    /// The symmetry after this one in `ALL`, wrapping around.
+    pub fn next(self) -> Symmetry {
+        let index = Symmetry::ALL
+            .iter()
+            .position(|symmetry| *symmetry == self)
+            .unwrap_or(0);
+
+        Symmetry::ALL[(index + 1) % Symmetry::ALL.len()]
+    }
+
+    /// `position` and every position it maps to around `center`, without duplicates.
+    pub fn images(self, center: Axial, position: Axial) -> Vec<Axial> {
+        let relative = axial_to_cube(position - center);
+
+        let cubes = match self {
+            Symmetry::Off => vec![relative],
+            Symmetry::MirrorLeftRight => vec![relative, mirror_left_right(relative)],
+            Symmetry::MirrorTopBottom => vec![relative, mirror_top_bottom(relative)],
+            Symmetry::Rotate(folds) => {
+                // Hexagons only line up again after turns of a multiple of 60 degrees.
+                assert!(
+                    folds > 0 && 6 % folds == 0,
+                    "rotational symmetry must be 1, 2, 3 or 6-fold"
+                );
+
+                let turns = 6 / folds;
+                let mut cube = relative;
+
+                (0..folds)
+                    .map(|_| {
+                        let image = cube;
+                        for _ in 0..turns {
+                            cube = rotate_60(cube);
+                        }
+                        image
+                    })
+                    .collect()
+            }
+        };
+
+        let mut images = Vec::new();
+
+        for cube in cubes {
+            let image = center + cube_to_axial(cube);
+
+            if !images.contains(&image) {
+                images.push(image);
+            }
+        }
+
+        images
+    }
+}
+
+/// Rotates clockwise on screen by 60 degrees around the origin.
+fn rotate_60(cube: Cube) -> Cube {
+    Cube::new(-cube.z, -cube.x, -cube.y)
+}
+
+fn mirror_left_right(cube: Cube) -> Cube {
+    Cube::new(-cube.x, -cube.z, -cube.y)
+}
+
+fn mirror_top_bottom(cube: Cube) -> Cube {
+    Cube::new(cube.x, cube.z, cube.y)
+}
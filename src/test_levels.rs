@@ -0,0 +1,12 @@
+//! Small levels for the unit tests, written in the native format.
+
+use crate::{
+    formats::{read_level, Format},
+    level::Level,
+};
+
+/// Reads cells and line hints written as in the native format, without its header line.
+pub fn level(items: &str) -> Level {
+    let text = format!("OpenCells level v1\n{}", items);
+    read_level(&text, Format::Native).unwrap()
+}
@@ -0,0 +1,349 @@
+//! Colors the game and its exports are drawn with.
+//!
+//! Themes can be loaded from files with one setting per line, for example:
+//!
+//! ```text
+//! OpenCells theme v1
+//! name Dusk
+//! background #101820
+//! hidden #f2aa4c
+//! marked #2d6cdf
+//! hover 1.3
+//! patterns on
+//! ```
+//!
+//! Colors are `background`, `hidden`, `empty`, `marked`, `border` and `text`, given as `#rrggbb`.
+//! `hover` brightens the hovered cell by a factor and `patterns` (`on` or `off`) adds stripes to
+//! hidden cells and a ring to marked ones, so they can be told apart without color. Settings left
+//! out are taken from the default theme, and the name from the file name. Empty lines and lines
+//! starting with `#` are ignored.
+
+use std::{fmt, fs, path::Path};
+
+use cgmath::Vector3;
+
+use crate::level::Hex;
+
+const HEADER: &str = "OpenCells theme v1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Vector3<f32>,
+    /// Cells that haven't been revealed yet.
+    pub hidden: Vector3<f32>,
+    pub empty: Vector3<f32>,
+    pub marked: Vector3<f32>,
+    pub border: Vector3<f32>,
+    pub text: Vector3<f32>,
+    /// How much brighter the hovered cell is drawn.
+    pub hover: f32,
+    /// Tells hidden and marked cells apart by pattern as well as by color.
+    pub patterns: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 4] = ["dark", "high-contrast", "colorblind", "print"];
+
+    /// The original look of the game.
+    pub fn dark() -> Theme {
+        Theme {
+            name: "dark".to_string(),
+            background: Vector3::new(0.1, 0.1, 0.1),
+            hidden: Vector3::new(0.960, 0.505, 0.058),
+            empty: Vector3::new(0.368, 0.368, 0.368),
+            marked: Vector3::new(0.058, 0.533, 0.960),
+            border: Vector3::new(1.0, 1.0, 1.0),
+            text: Vector3::new(1.0, 1.0, 1.0),
+            hover: 1.5,
+            patterns: false,
+        }
+    }
+
+    /// Bright cells outlined on black, for low vision.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high-contrast".to_string(),
+            background: Vector3::new(0.0, 0.0, 0.0),
+            hidden: Vector3::new(1.0, 0.85, 0.0),
+            empty: Vector3::new(0.0, 0.0, 0.0),
+            marked: Vector3::new(0.0, 0.45, 1.0),
+            border: Vector3::new(1.0, 1.0, 1.0),
+            text: Vector3::new(1.0, 1.0, 1.0),
+            hover: 1.8,
+            patterns: true,
+        }
+    }
+
+    /// The orange and blue of the Okabe-Ito palette, which stay distinct with the common kinds
+    /// of color blindness, along with patterns.
+    pub fn colorblind() -> Theme {
+        Theme {
+            name: "colorblind".to_string(),
+            background: Vector3::new(0.1, 0.1, 0.1),
+            hidden: Vector3::new(0.902, 0.624, 0.0),
+            empty: Vector3::new(0.35, 0.35, 0.35),
+            marked: Vector3::new(0.0, 0.447, 0.698),
+            border: Vector3::new(1.0, 1.0, 1.0),
+            text: Vector3::new(1.0, 1.0, 1.0),
+            hover: 1.4,
+            patterns: true,
+        }
+    }
+
+    /// Light colors with dark lines and text, for printing on paper.
+    pub fn print() -> Theme {
+        Theme {
+            name: "print".to_string(),
+            background: Vector3::new(1.0, 1.0, 1.0),
+            hidden: Vector3::new(0.85, 0.85, 0.85),
+            empty: Vector3::new(1.0, 1.0, 1.0),
+            marked: Vector3::new(0.45, 0.45, 0.45),
+            border: Vector3::new(0.0, 0.0, 0.0),
+            text: Vector3::new(0.0, 0.0, 0.0),
+            hover: 0.9,
+            patterns: false,
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "colorblind" => Some(Theme::colorblind()),
+            "print" => Some(Theme::print()),
+            _ => None,
+        }
+    }
+
+    /// The built-in theme after this one, for cycling through them.
+    pub fn next_built_in(&self) -> Theme {
+        let index = Theme::BUILT_IN
+            .iter()
+            .position(|name| *name == self.name)
+            .map_or(0, |index| (index + 1) % Theme::BUILT_IN.len());
+
+        Theme::built_in(Theme::BUILT_IN[index]).unwrap()
+    }
+
+    /// A built-in theme by name, or else a theme file at that path.
+    pub fn load(name_or_path: &str) -> Result<Theme, String> {
+        if let Some(theme) = Theme::built_in(name_or_path) {
+            return Ok(theme);
+        }
+
+        let text = fs::read_to_string(name_or_path)
+            .map_err(|error| format!("couldn't read {}: {}", name_or_path, error))?;
+
+        let mut theme =
+            Theme::parse(&text).map_err(|error| format!("{}: {}", name_or_path, error))?;
+
+        if theme.name.is_empty() {
+            let stem = Path::new(name_or_path).file_stem().unwrap_or_default();
+            theme.name = stem.to_string_lossy().into_owned();
+        }
+
+        Ok(theme)
+    }
+
+    /// Reads a theme file's text. The name is left empty if the file doesn't give one.
+    pub fn parse(text: &str) -> Result<Theme, ThemeError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => {}
+            _ => return Err(ThemeError::new(1, format!("expected `{}`", HEADER))),
+        }
+
+        let mut theme = Theme {
+            name: String::new(),
+            ..Theme::dark()
+        };
+
+        for (number, line) in lines {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find(' ') {
+                Some(space) => (&line[..space], line[space + 1..].trim()),
+                None => (line, ""),
+            };
+
+            let color = || {
+                parse_color(value)
+                    .ok_or_else(|| ThemeError::new(number, format!("invalid color: {}", value)))
+            };
+
+            match key {
+                "name" => theme.name = value.to_string(),
+                "background" => theme.background = color()?,
+                "hidden" => theme.hidden = color()?,
+                "empty" => theme.empty = color()?,
+                "marked" => theme.marked = color()?,
+                "border" => theme.border = color()?,
+                "text" => theme.text = color()?,
+                "hover" => {
+                    theme.hover = value
+                        .parse()
+                        .ok()
+                        .filter(|factor: &f32| factor.is_finite() && *factor >= 0.0)
+                        .ok_or_else(|| {
+                            ThemeError::new(number, format!("invalid factor: {}", value))
+                        })?
+                }
+                "patterns" => {
+                    theme.patterns = match value {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(ThemeError::new(number, "expected `on` or `off`")),
+                    }
+                }
+                _ => return Err(ThemeError::new(number, format!("unknown setting: {}", key))),
+            }
+        }
+
+        Ok(theme)
+    }
+
+    pub fn cell_color(&self, hex: Hex, revealed: bool) -> Vector3<f32> {
+        match hex {
+            _ if !revealed => self.hidden,
+            Hex::Empty { .. } => self.empty,
+            Hex::Marked { .. } => self.marked,
+        }
+    }
+
+    pub fn background_color(&self) -> [f32; 4] {
+        [self.background.x, self.background.y, self.background.z, 1.0]
+    }
+
+    pub fn text_color(&self) -> [f32; 4] {
+        [self.text.x, self.text.y, self.text.z, 1.0]
+    }
+}
+
+fn parse_color(text: &str) -> Option<Vector3<f32>> {
+    let hex = text.strip_prefix('#')?;
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| {
+        u8::from_str_radix(&hex[index..index + 2], 16)
+            .ok()
+            .map(|value| value as f32 / 255.0)
+    };
+
+    Some(Vector3::new(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl ThemeError {
+    fn new(line: usize, reason: impl Into<String>) -> ThemeError {
+        ThemeError {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn parse(items: &str) -> Result<Theme, ThemeError> {
+        Theme::parse(&format!("{}\n{}", HEADER, items))
+    }
+
+    #[test]
+    fn settings_replace_the_defaults() {
+        let theme = parse(
+            "# A comment
+             name Dusk
+
+             background #101820
+             marked #2D6CDF
+             hover 1.3
+             patterns on",
+        )
+        .unwrap();
+
+        assert_eq!(
+            theme,
+            Theme {
+                name: "Dusk".to_string(),
+                background: parse_color("#101820").unwrap(),
+                marked: parse_color("#2d6cdf").unwrap(),
+                hover: 1.3,
+                patterns: true,
+                ..Theme::dark()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_reported() {
+        let error = |items: &str| parse(items).unwrap_err().to_string();
+
+        assert_eq!(
+            Theme::parse("name Dusk").unwrap_err().to_string(),
+            format!("line 1: expected `{}`", HEADER)
+        );
+        assert_eq!(
+            error("name Dusk\nglow #ffffff"),
+            "line 3: unknown setting: glow"
+        );
+        assert_eq!(error("hidden #ffff"), "line 2: invalid color: #ffff");
+        assert_eq!(error("hidden #gg0000"), "line 2: invalid color: #gg0000");
+        assert_eq!(error("patterns maybe"), "line 2: expected `on` or `off`");
+
+        for factor in &["-0.5", "NaN", "inf", "bright"] {
+            assert_eq!(
+                error(&format!("hover {}", factor)),
+                format!("line 2: invalid factor: {}", factor)
+            );
+        }
+    }
+
+    #[test]
+    fn files_without_a_name_are_named_after_the_file() {
+        let path = env::temp_dir().join(format!("opencells-theme-{}.theme", std::process::id()));
+        fs::write(&path, format!("{}\nhover 1.5\n", HEADER)).unwrap();
+
+        let theme = Theme::load(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+
+        let theme = theme.unwrap();
+        assert_eq!(
+            theme.name,
+            format!("opencells-theme-{}", std::process::id())
+        );
+        assert_eq!(theme.hover, 1.5);
+    }
+}
@@ -0,0 +1,396 @@
+//! A small immediate-mode interface drawn over the level. Widgets are laid out and handle input
+//! in the same call each update, so menus and panels are plain code that runs while they are
+//! open:
+//!
+//! ```ignore
+//! ui.begin(input);
+//! let mut layout = ui.dialog(viewport_size, Vector2::new(400.0, 300.0), "Menu");
+//! ui.label(&mut layout, "Theme");
+//! if ui.button(&mut layout, "close", "Close") {
+//!     open = false;
+//! }
+//! ```
+//!
+//! Only the pointer and typed text reach the widgets. Input over a panel or anywhere while a
+//! dialog is open is kept from the level, and a focused text input takes the keyboard.
+
+use cgmath::{Vector2, Zero};
+
+use opencells_rs::scene::{text_width, Anchor, DrawRect, DrawText};
+
+pub const ROW_HEIGHT: f32 = 32.0;
+pub const SPACING: f32 = 8.0;
+const PADDING: f32 = 16.0;
+const TEXT_SIZE: f32 = 22.0;
+const TITLE_SIZE: f32 = 28.0;
+
+const PANEL_COLOR: [f32; 4] = [0.16, 0.16, 0.18, 0.95];
+const SHADE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const WIDGET_COLOR: [f32; 4] = [0.26, 0.26, 0.3, 1.0];
+const HOVERED_COLOR: [f32; 4] = [0.34, 0.34, 0.4, 1.0];
+const PRESSED_COLOR: [f32; 4] = [0.2, 0.2, 0.24, 1.0];
+const SELECTED_COLOR: [f32; 4] = [0.2, 0.4, 0.7, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const FOCUS_COLOR: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+
+/// An area on screen, in pixels from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub position: Vector2<f32>,
+    pub size: Vector2<f32>,
+}
+
+impl Rect {
+    pub fn new(position: Vector2<f32>, size: Vector2<f32>) -> Rect {
+        Rect { position, size }
+    }
+
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        let relative = point - self.position;
+        relative.x >= 0.0
+            && relative.y >= 0.0
+            && relative.x <= self.size.x
+            && relative.y <= self.size.y
+    }
+
+    pub fn center(&self) -> Vector2<f32> {
+        self.position + self.size / 2.0
+    }
+
+    /// The rectangle shrunk by `amount` on every side.
+    pub fn inset(&self, amount: f32) -> Rect {
+        Rect::new(
+            self.position + Vector2::new(amount, amount),
+            self.size - Vector2::new(amount, amount) * 2.0,
+        )
+    }
+}
+
+/// Places widgets one below the other, each as wide as the column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    cursor: Vector2<f32>,
+    width: f32,
+}
+
+impl Layout {
+    /// A column filling `area`, starting at its top.
+    pub fn column(area: Rect) -> Layout {
+        Layout {
+            cursor: area.position,
+            width: area.size.x,
+        }
+    }
+
+    /// Takes the next `height` pixels of the column.
+    pub fn next(&mut self, height: f32) -> Rect {
+        let rect = Rect::new(self.cursor, Vector2::new(self.width, height));
+        self.cursor.y += height + SPACING;
+        rect
+    }
+
+    /// Splits the next row into `count` equal cells side by side.
+    pub fn row(&mut self, count: usize) -> Vec<Rect> {
+        let row = self.next(ROW_HEIGHT);
+        let count = count.max(1) as f32;
+        let width = (row.size.x - SPACING * (count - 1.0)) / count;
+
+        (0..count as usize)
+            .map(|index| {
+                let x = row.position.x + index as f32 * (width + SPACING);
+                Rect::new(
+                    Vector2::new(x, row.position.y),
+                    Vector2::new(width, ROW_HEIGHT),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The input the widgets see in an update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiInput {
+    pub pointer: Vector2<f32>,
+    /// Whether the primary button went down or up since the last update.
+    pub pressed: bool,
+    pub released: bool,
+    /// Characters typed since the last update, including backspace, enter and escape.
+    pub typed: String,
+}
+
+#[derive(Debug)]
+pub struct Ui {
+    /// The widget the pointer went down on, which is clicked if it's also released there.
+    active: Option<String>,
+    /// The text input taking the keyboard.
+    focused: Option<String>,
+
+    input: UiInput,
+    /// Whether a panel or dialog is under the pointer, or a dialog is open.
+    captures_pointer: bool,
+    /// Whether the pointer went down on the focused text input this update.
+    keeps_focus: bool,
+
+    pub rects: Vec<DrawRect>,
+    pub texts: Vec<DrawText>,
+}
+
+impl Default for UiInput {
+    fn default() -> Self {
+        UiInput {
+            pointer: Vector2::zero(),
+            pressed: false,
+            released: false,
+            typed: String::new(),
+        }
+    }
+}
+
+impl Ui {
+    pub fn new() -> Ui {
+        Ui {
+            active: None,
+            focused: None,
+            input: UiInput::default(),
+            captures_pointer: false,
+            keeps_focus: false,
+            rects: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Starts an update, clearing what was drawn for the last one.
+    pub fn begin(&mut self, input: UiInput) {
+        self.input = input;
+        self.captures_pointer = false;
+        self.keeps_focus = false;
+        self.rects.clear();
+        self.texts.clear();
+    }
+
+    /// Ends an update. Pressing outside every text input takes the focus away.
+    pub fn end(&mut self) {
+        if self.input.pressed && !self.keeps_focus {
+            self.focused = None;
+        }
+
+        if self.input.released {
+            self.active = None;
+        }
+    }
+
+    /// Whether the level shouldn't get the pointer this update.
+    pub fn captures_pointer(&self) -> bool {
+        self.captures_pointer
+    }
+
+    /// Whether the level shouldn't get the keyboard this update.
+    pub fn captures_keyboard(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    fn rect(&mut self, rect: Rect, color: [f32; 4]) {
+        self.rects.push(DrawRect {
+            position: rect.position,
+            size: rect.size,
+            color,
+        });
+    }
+
+    fn text(&mut self, text: &str, position: Vector2<f32>, size: f32, anchor: Anchor) {
+        self.texts.push(DrawText {
+            text: text.to_string(),
+            position,
+            size,
+            color: TEXT_COLOR,
+            anchor,
+            rotation: 0.0,
+        });
+    }
+
+    /// Draws a panel and returns a column inside it. The panel keeps the pointer from the level.
+    pub fn panel(&mut self, area: Rect) -> Layout {
+        self.rect(area, PANEL_COLOR);
+
+        if area.contains(self.input.pointer) {
+            self.captures_pointer = true;
+        }
+
+        Layout::column(area.inset(PADDING))
+    }
+
+    /// Shades the whole window and draws a titled panel centered in it, returning a column below
+    /// the title. Nothing but the dialog gets the pointer while it's open.
+    pub fn dialog(
+        &mut self,
+        viewport_size: Vector2<f32>,
+        size: Vector2<f32>,
+        title: &str,
+    ) -> Layout {
+        self.rect(
+            Rect::new(Vector2::new(0.0, 0.0), viewport_size),
+            SHADE_COLOR,
+        );
+        self.captures_pointer = true;
+
+        let area = Rect::new((viewport_size - size) / 2.0, size);
+        let mut layout = self.panel(area);
+
+        let title_row = layout.next(TITLE_SIZE * 1.25);
+        self.text(title, title_row.center(), TITLE_SIZE, Anchor::Center);
+
+        layout
+    }
+
+    pub fn label(&mut self, layout: &mut Layout, text: &str) {
+        let row = layout.next(ROW_HEIGHT);
+        self.text(text, text_position(row), TEXT_SIZE, Anchor::TopLeft);
+    }
+
+    /// Tracks the pointer on a widget, returning whether it was clicked: pressed and released
+    /// over it.
+    fn interact(&mut self, id: &str, area: Rect) -> bool {
+        let hovered = area.contains(self.input.pointer);
+
+        if hovered && self.input.pressed {
+            self.active = Some(id.to_string());
+        }
+
+        let active = self.active.as_deref() == Some(id);
+        active && hovered && self.input.released
+    }
+
+    fn widget_color(&self, id: &str, area: Rect) -> [f32; 4] {
+        let hovered = area.contains(self.input.pointer);
+
+        match self.active.as_deref() {
+            Some(active) if active == id && hovered => PRESSED_COLOR,
+            _ if hovered => HOVERED_COLOR,
+            _ => WIDGET_COLOR,
+        }
+    }
+
+    /// A button filling the next row, returning whether it was clicked.
+    pub fn button(&mut self, layout: &mut Layout, id: &str, label: &str) -> bool {
+        let area = layout.next(ROW_HEIGHT);
+        self.button_in(area, id, label)
+    }
+
+    /// A button filling `area`, for buttons laid out side by side with `Layout::row`. The id
+    /// keeps a press on the button while its label changes.
+    pub fn button_in(&mut self, area: Rect, id: &str, label: &str) -> bool {
+        let id = format!("button:{}", id);
+        let color = self.widget_color(&id, area);
+
+        self.rect(area, color);
+        self.text(label, area.center(), TEXT_SIZE, Anchor::Center);
+
+        self.interact(&id, area)
+    }
+
+    /// A list with one row per item and `selected` highlighted, returning the item clicked.
+    pub fn list(
+        &mut self,
+        layout: &mut Layout,
+        id: &str,
+        items: &[&str],
+        selected: Option<usize>,
+    ) -> Option<usize> {
+        let area = layout.next(items.len() as f32 * ROW_HEIGHT);
+        let mut clicked = None;
+
+        self.rect(area, WIDGET_COLOR);
+
+        for (index, item) in items.iter().enumerate() {
+            let row = Rect::new(
+                area.position + Vector2::new(0.0, index as f32 * ROW_HEIGHT),
+                Vector2::new(area.size.x, ROW_HEIGHT),
+            );
+            let row_id = format!("list:{}:{}", id, index);
+
+            let color = if selected == Some(index) {
+                SELECTED_COLOR
+            } else {
+                self.widget_color(&row_id, row)
+            };
+
+            self.rect(row, color);
+            self.text(
+                item,
+                text_position(row) + Vector2::new(SPACING, 0.0),
+                TEXT_SIZE,
+                Anchor::TopLeft,
+            );
+
+            if self.interact(&row_id, row) {
+                clicked = Some(index);
+            }
+        }
+
+        clicked
+    }
+
+    /// A single line of editable text. Pressing on it takes the keyboard until enter, escape or
+    /// a press elsewhere. Returns whether the text changed.
+    pub fn text_input(&mut self, layout: &mut Layout, id: &str, text: &mut String) -> bool {
+        let area = layout.next(ROW_HEIGHT);
+        let id = format!("input:{}", id);
+
+        if self.input.pressed && area.contains(self.input.pointer) {
+            self.focused = Some(id.clone());
+            self.keeps_focus = true;
+        }
+
+        let focused = self.focused.as_deref() == Some(id.as_str());
+        let mut changed = false;
+
+        if focused {
+            for c in self.input.typed.chars() {
+                match c {
+                    '\u{8}' | '\u{7f}' => changed |= text.pop().is_some(),
+                    '\r' | '\n' | '\u{1b}' => self.focused = None,
+                    c if !c.is_control() => {
+                        text.push(c);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let color = self.widget_color(&id, area);
+        self.rect(area, color);
+
+        if focused {
+            // Outlines the input and puts a caret after the text.
+            let outline = 2.0;
+            let top = Rect::new(area.position, Vector2::new(area.size.x, outline));
+            let bottom = Rect::new(
+                area.position + Vector2::new(0.0, area.size.y - outline),
+                Vector2::new(area.size.x, outline),
+            );
+            self.rect(top, FOCUS_COLOR);
+            self.rect(bottom, FOCUS_COLOR);
+
+            let caret_x = area.position.x + SPACING + text_width(text, TEXT_SIZE) + 1.0;
+            let caret = Rect::new(
+                Vector2::new(caret_x, area.position.y + 6.0),
+                Vector2::new(2.0, ROW_HEIGHT - 12.0),
+            );
+            self.rect(caret, FOCUS_COLOR);
+        }
+
+        let position = text_position(area) + Vector2::new(SPACING, 0.0);
+        self.text(text, position, TEXT_SIZE, Anchor::TopLeft);
+
+        changed
+    }
+}
+
+/// Where text starts to sit vertically centered in a row.
+fn text_position(row: Rect) -> Vector2<f32> {
+    Vector2::new(
+        row.position.x,
+        row.position.y + (row.size.y - TEXT_SIZE * 1.2) / 2.0,
+    )
+}
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+
+use crate::{
+    hexagon::Axial,
+    level::Level,
+    rules::{Puzzle, Solution},
+    search::Search,
+};
+
+#[derive(Debug)]
+pub struct AlternativeSolution {
+    pub solution: Solution,
+    /// Cells whose marked state differs from the designed solution.
+    pub differing: Vec<Axial>,
+}
+
+#[derive(Debug)]
+pub enum Uniqueness {
+    Unique,
+    Ambiguous(AlternativeSolution),
+}
+
+/// Exhaustively checks whether the designed layout is the only assignment of the hidden cells
+/// that agrees with every hint it would display and with the total number of marked cells.
+pub fn check_uniqueness(level: &Level) -> Uniqueness {
+    let puzzle = Puzzle::from_level(level);
+    check_puzzle_uniqueness(&puzzle)
+}
+
+/// Hidden cells that no hint connects are searched independently and in parallel. Only the total
+/// count ties them together, so an alternative is either local to one group with the same number
+/// of marked cells, or combines groups whose changes in marked cells cancel out. The alternative
+/// reported is the same on every run, from the first group that has one.
+pub fn check_puzzle_uniqueness(puzzle: &Puzzle) -> Uniqueness {
+    check_puzzle_uniqueness_until(puzzle, &|| false).unwrap()
+}
+
+/// Checks uniqueness like `check_puzzle_uniqueness`, giving up with `None` once `cancelled`
+/// returns true.
+pub fn check_puzzle_uniqueness_until(
+    puzzle: &Puzzle,
+    cancelled: &(dyn Fn() -> bool + Sync),
+) -> Option<Uniqueness> {
+    let components: Vec<(Vec<usize>, Puzzle)> = puzzle
+        .components()
+        .into_iter()
+        .map(|cells| {
+            let subpuzzle = puzzle.subpuzzle(&cells);
+            (cells, subpuzzle)
+        })
+        .collect();
+
+    let local = components.par_iter().find_map_first(|(cells, subpuzzle)| {
+        find_alternative(subpuzzle, cancelled).map(|values| vec![(cells, values)])
+    });
+
+    let changes = local.or_else(|| {
+        let shifted: Vec<Vec<(isize, Vec<bool>)>> = components
+            .par_iter()
+            .map(|(_, subpuzzle)| shifted_alternatives(subpuzzle, cancelled))
+            .collect();
+
+        balanced_choice(&shifted).map(|choice| {
+            choice
+                .into_iter()
+                .map(|(component, option)| {
+                    (
+                        &components[component].0,
+                        shifted[component][option].1.clone(),
+                    )
+                })
+                .collect()
+        })
+    });
+
+    // A cancelled search stops without finding anything, which looks like a unique solution.
+    if cancelled() {
+        return None;
+    }
+
+    Some(match changes {
+        None => Uniqueness::Unique,
+        Some(changes) => {
+            let mut values = puzzle.solution.clone();
+
+            for (cells, local_values) in changes {
+                for (local, &cell) in cells.iter().enumerate() {
+                    values[cell] = local_values[local];
+                }
+            }
+
+            Uniqueness::Ambiguous(alternative_solution(puzzle, &values))
+        }
+    })
+}
+
+fn find_alternative(puzzle: &Puzzle, cancelled: &dyn Fn() -> bool) -> Option<Vec<bool>> {
+    let mut search = Search::cancellable(puzzle, cancelled);
+    let mut alternative = None;
+
+    search.for_each_solution(&mut |values| {
+        if values == puzzle.solution.as_slice() {
+            return false;
+        }
+
+        alternative = Some(values.to_vec());
+        true
+    });
+
+    alternative
+}
+
+/// Finds a solution for every other number of marked cells the subpuzzle could hold, keyed by
+/// how many more marked cells it has than the designed solution.
+fn shifted_alternatives(
+    puzzle: &Puzzle,
+    cancelled: &(dyn Fn() -> bool + Sync),
+) -> Vec<(isize, Vec<bool>)> {
+    let total = puzzle.total;
+    let designed = puzzle.constraints[total].count as isize;
+    let cells = puzzle.constraints[total].scope.len() as isize;
+
+    (-designed..=cells - designed)
+        .into_par_iter()
+        .filter(|&delta| delta != 0)
+        .filter_map(|delta| {
+            let mut shifted = puzzle.clone();
+            shifted.constraints[total].count = (designed + delta) as usize;
+
+            let mut search = Search::cancellable(&shifted, cancelled);
+            let mut found = None;
+
+            search.for_each_solution(&mut |values| {
+                found = Some(values.to_vec());
+                true
+            });
+
+            found.map(|values| (delta, values))
+        })
+        .collect()
+}
+
+/// Picks at most one shifted alternative per component so that the shifts cancel out.
+fn balanced_choice(shifted: &[Vec<(isize, Vec<bool>)>]) -> Option<Vec<(usize, usize)>> {
+    let mut sums: BTreeMap<isize, Vec<(usize, usize)>> = BTreeMap::new();
+
+    for (component, options) in shifted.iter().enumerate() {
+        let previous: Vec<(isize, Vec<(usize, usize)>)> = sums
+            .iter()
+            .map(|(sum, choice)| (*sum, choice.clone()))
+            .chain(std::iter::once((0, Vec::new())))
+            .collect();
+
+        for (sum, choice) in previous {
+            for (option, (delta, _)) in options.iter().enumerate() {
+                let mut extended = choice.clone();
+                extended.push((component, option));
+
+                if sum + delta == 0 {
+                    return Some(extended);
+                }
+
+                sums.entry(sum + delta).or_insert(extended);
+            }
+        }
+    }
+
+    None
+}
+
+fn alternative_solution(puzzle: &Puzzle, values: &[bool]) -> AlternativeSolution {
+    let differing = (0..puzzle.len())
+        .filter(|&cell| values[cell] != puzzle.solution[cell])
+        .map(|cell| puzzle.positions[cell])
+        .collect();
+
+    AlternativeSolution {
+        solution: puzzle.to_solution(values),
+        differing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_levels::level;
+
+    /// Checks that an alternative is a different, valid solution and that `differing` lists
+    /// exactly the cells that changed.
+    fn assert_alternative(level: &Level, alternative: &AlternativeSolution) {
+        let puzzle = Puzzle::from_level(level);
+        let values: Vec<bool> = puzzle
+            .positions
+            .iter()
+            .map(|position| alternative.solution[position])
+            .collect();
+
+        assert_ne!(values, puzzle.solution);
+
+        let mut differing: Vec<Axial> = puzzle
+            .positions
+            .iter()
+            .zip(values.iter().zip(&puzzle.solution))
+            .filter(|(_, (value, designed))| value != designed)
+            .map(|(position, _)| *position)
+            .collect();
+        let mut reported = alternative.differing.clone();
+        differing.sort_by_key(|position| (position.x, position.y));
+        reported.sort_by_key(|position| (position.x, position.y));
+        assert_eq!(reported, differing);
+
+        let mut found = false;
+        Search::new(&puzzle).for_each_solution(&mut |solution| {
+            found = solution == values.as_slice();
+            found
+        });
+        assert!(found, "the alternative breaks a hint");
+    }
+
+    #[test]
+    fn empty_level_is_unique() {
+        assert!(matches!(
+            check_uniqueness(&Level::new()),
+            Uniqueness::Unique
+        ));
+    }
+
+    #[test]
+    fn forced_level_is_unique() {
+        let level = level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty
+             line 0 -1 down",
+        );
+
+        assert!(matches!(check_uniqueness(&level), Uniqueness::Unique));
+    }
+
+    #[test]
+    fn unbalanced_shift_stays_unique() {
+        // The far cell could be marked, but nothing else can give up a marked cell for it.
+        let level = level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 5 5 empty",
+        );
+
+        assert!(matches!(check_uniqueness(&level), Uniqueness::Unique));
+    }
+
+    #[test]
+    fn swapped_neighbors_are_ambiguous() {
+        let level = level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        );
+
+        match check_uniqueness(&level) {
+            Uniqueness::Ambiguous(alternative) => {
+                assert_alternative(&level, &alternative);
+                assert_eq!(alternative.differing.len(), 2);
+            }
+            Uniqueness::Unique => panic!("expected an alternative"),
+        }
+    }
+
+    #[test]
+    fn hidden_host_is_ambiguous() {
+        let level = level(
+            "cell 0 0 empty number
+             cell 1 0 marked",
+        );
+
+        match check_uniqueness(&level) {
+            Uniqueness::Ambiguous(alternative) => {
+                assert_alternative(&level, &alternative);
+                assert!(alternative.solution[&Axial::new(0, 0)]);
+            }
+            Uniqueness::Unique => panic!("expected an alternative"),
+        }
+    }
+
+    #[test]
+    fn balanced_shifts_across_components_are_ambiguous() {
+        let level = level(
+            "cell 0 0 marked
+             cell 5 5 empty",
+        );
+
+        match check_uniqueness(&level) {
+            Uniqueness::Ambiguous(alternative) => assert_alternative(&level, &alternative),
+            Uniqueness::Unique => panic!("expected an alternative"),
+        }
+    }
+
+    #[test]
+    fn first_alternative_is_reported() {
+        let level = level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty
+             cell 10 0 empty number revealed
+             cell 11 0 marked
+             cell 10 1 empty",
+        );
+
+        for _ in 0..20 {
+            match check_uniqueness(&level) {
+                Uniqueness::Ambiguous(alternative) => {
+                    let mut differing = alternative.differing;
+                    differing.sort_by_key(|position| (position.x, position.y));
+                    assert_eq!(differing, [Axial::new(0, 1), Axial::new(1, 0)]);
+                }
+                Uniqueness::Unique => panic!("expected an alternative"),
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_check_gives_up() {
+        let puzzle = Puzzle::from_level(&level(
+            "cell 0 0 empty number revealed
+             cell 1 0 marked
+             cell 0 1 empty",
+        ));
+
+        assert!(check_puzzle_uniqueness_until(&puzzle, &|| true).is_none());
+        assert!(matches!(
+            check_puzzle_uniqueness_until(&puzzle, &|| false),
+            Some(Uniqueness::Ambiguous(_))
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    formats::fits_hexcells_grid,
+    hexagon::Axial,
+    level::{Hex, Level},
+    rules::{HintSource, Puzzle},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Probably a mistake, but the level can still be played.
+    Warning,
+    /// The level can't be played as intended.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    NoCells,
+    NoMarkedCells,
+    /// A line hint with no cells on its line.
+    EmptyLine,
+    /// A line hint displayed on top of a cell.
+    LineHintOnCell,
+    /// A count shown on a cell that has nothing around it to count.
+    CountWithoutCells,
+    /// A `{n}` or `-n-` that doesn't describe the cells it counts.
+    WrongModifier,
+    /// A modifier on a count below two, where it can't tell anything.
+    UselessModifier,
+    /// Cells that don't touch the rest of the level.
+    Island,
+    /// A marked cell revealed at the start, giving away part of the solution.
+    RevealedMarked,
+    /// The level is larger than Hexcells' 33 by 33 grid.
+    ExceedsHexcellsGrid,
+}
+
+impl DiagnosticKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            DiagnosticKind::NoCells
+            | DiagnosticKind::NoMarkedCells
+            | DiagnosticKind::EmptyLine
+            | DiagnosticKind::LineHintOnCell
+            | DiagnosticKind::WrongModifier => Severity::Error,
+            DiagnosticKind::CountWithoutCells
+            | DiagnosticKind::UselessModifier
+            | DiagnosticKind::Island
+            | DiagnosticKind::RevealedMarked
+            | DiagnosticKind::ExceedsHexcellsGrid => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// Cells or line hints the diagnostic is about, empty if it's about the whole level.
+    pub positions: Vec<Axial>,
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, positions: Vec<Axial>) -> Diagnostic {
+        Diagnostic { kind, positions }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self.kind {
+            DiagnosticKind::NoCells => "the level has no cells",
+            DiagnosticKind::NoMarkedCells => "the level has no marked cells",
+            DiagnosticKind::EmptyLine => "line hint with no cells on its line",
+            DiagnosticKind::LineHintOnCell => "line hint on top of a cell",
+            DiagnosticKind::CountWithoutCells => "count with no cells to count",
+            DiagnosticKind::WrongModifier => "modifier doesn't match the counted cells",
+            DiagnosticKind::UselessModifier => "modifier on a count below two",
+            DiagnosticKind::Island => "cells not connected to the rest of the level",
+            DiagnosticKind::RevealedMarked => "marked cell revealed at the start",
+            DiagnosticKind::ExceedsHexcellsGrid => "the level doesn't fit in Hexcells' grid",
+        };
+
+        let severity = match self.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{}: {}", severity, message)?;
+
+        for (index, position) in self.positions.iter().enumerate() {
+            let separator = if index == 0 { " at" } else { "" };
+            write!(f, "{} ({}, {})", separator, position.x, position.y)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds problems with a level's layout and hints, errors first. Doesn't check whether the
+/// level can be solved.
+pub fn validate(level: &Level) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if level.cells.is_empty() {
+        diagnostics.push(Diagnostic::new(DiagnosticKind::NoCells, Vec::new()));
+        return diagnostics;
+    }
+
+    let positions = level.sorted_positions();
+
+    if !positions.iter().any(|position| level.is_marked(*position)) {
+        diagnostics.push(Diagnostic::new(DiagnosticKind::NoMarkedCells, Vec::new()));
+    }
+
+    let mut origins: Vec<Axial> = level.line_hints.keys().copied().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    for origin in &origins {
+        if level.cells.contains_key(origin) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::LineHintOnCell,
+                vec![*origin],
+            ));
+        }
+
+        if level
+            .line_cells(*origin, level.line_hints[origin].direction)
+            .is_empty()
+        {
+            diagnostics.push(Diagnostic::new(DiagnosticKind::EmptyLine, vec![*origin]));
+        }
+    }
+
+    let puzzle = Puzzle::from_level(level);
+
+    for constraint in &puzzle.constraints {
+        let position = match constraint.source {
+            HintSource::Neighbors(position)
+            | HintSource::Around(position)
+            | HintSource::Line(position) => position,
+            HintSource::Total => continue,
+        };
+
+        let is_cell_count = !matches!(constraint.source, HintSource::Line(_));
+
+        if is_cell_count && constraint.scope.is_empty() {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::CountWithoutCells,
+                vec![position],
+            ));
+        }
+
+        if let Some(arrangement) = &constraint.arrangement {
+            if !arrangement.is_satisfied(|cell| puzzle.solution[cell]) {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::WrongModifier,
+                    vec![position],
+                ));
+            } else if constraint.count < 2 {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::UselessModifier,
+                    vec![position],
+                ));
+            }
+        }
+    }
+
+    let mut islands = islands(level, &positions);
+    islands.sort_by_key(|island| std::cmp::Reverse(island.len()));
+
+    for island in islands.into_iter().skip(1) {
+        diagnostics.push(Diagnostic::new(DiagnosticKind::Island, island));
+    }
+
+    let revealed_marked: Vec<Axial> = positions
+        .iter()
+        .copied()
+        .filter(|position| {
+            let cell = &level.cells[position];
+            cell.start_revealed && matches!(cell.hex, Hex::Marked { .. })
+        })
+        .collect();
+
+    if !revealed_marked.is_empty() {
+        diagnostics.push(Diagnostic::new(
+            DiagnosticKind::RevealedMarked,
+            revealed_marked,
+        ));
+    }
+
+    if !fits_hexcells_grid(level) {
+        diagnostics.push(Diagnostic::new(
+            DiagnosticKind::ExceedsHexcellsGrid,
+            Vec::new(),
+        ));
+    }
+
+    diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity()));
+    diagnostics
+}
+
+/// Groups of cells connected through neighbors, each in sorted order.
+fn islands(level: &Level, positions: &[Axial]) -> Vec<Vec<Axial>> {
+    let mut visited = HashSet::new();
+    let mut islands = Vec::new();
+
+    for start in positions {
+        if visited.contains(start) {
+            continue;
+        }
+
+        let island = level.connected_cells(*start);
+        visited.extend(island.iter().copied());
+        islands.push(island);
+    }
+
+    islands
+}
@@ -0,0 +1,414 @@
+//! Builds the scene for what the game shows from plain data: the level, its hints, the theme and
+//! what the player is pointing at and has selected. The editor draws its frames with it and
+//! `scene::level_scene` draws its boards with it, so both can be snapshot tested without a window.
+
+use cgmath::{Vector2, Vector3};
+
+use crate::{
+    animation::Animations,
+    hexagon::{flat_hex_to_pixel, Axial},
+    hints::HintCache,
+    inspector,
+    level::{Hex, Level},
+    live::LiveAnalysis,
+    scene::{flat_line_hint_rotation, pattern_flags, DrawHex, Scene, SELECTED, START_REVEALED},
+    selection::Selection,
+    symmetry::Symmetry,
+    theme::Theme,
+    validate::{Diagnostic, Severity},
+};
+
+const SELECTION_TEXT_COLOR: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+/// How hovered the cells counted by the hovered line hint look.
+const LINE_HOVER: f32 = 0.6;
+
+/// Which cells show their kind and count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reveal {
+    /// Every cell shows its kind, and cells revealed at the start are marked.
+    Editing,
+    /// Cells show their kind once the player has revealed them and they have flipped over.
+    Playing,
+    /// Only the cells revealed at the start show their kind.
+    Start,
+    /// Every cell shows its kind, as when the level is solved.
+    Solution,
+}
+
+/// A level laid out on screen.
+#[derive(Clone, Copy)]
+pub struct Board<'a> {
+    pub level: &'a Level,
+    pub hints: &'a HintCache,
+    pub theme: &'a Theme,
+    /// Distance from a cell's center to its corners, in pixels.
+    pub scale: f32,
+    /// Where the level's origin is drawn.
+    pub offset: Vector2<f32>,
+    pub reveal: Reveal,
+    /// The hex under the pointer. A line hint there points out the cells it counts.
+    pub hovered: Option<Axial>,
+    /// Cells drawn as selected while editing.
+    pub selection: Option<&'a Selection>,
+    /// Cells a drag is about to select, drawn as selected as well.
+    pub preview: &'a [Axial],
+    pub animations: Option<&'a Animations>,
+}
+
+impl<'a> Board<'a> {
+    /// A board with nothing hovered, selected or moving.
+    pub fn new(
+        level: &'a Level,
+        hints: &'a HintCache,
+        theme: &'a Theme,
+        scale: f32,
+        reveal: Reveal,
+    ) -> Board<'a> {
+        Board {
+            level,
+            hints,
+            theme,
+            scale,
+            offset: Vector2::new(0.0, 0.0),
+            reveal,
+            hovered: None,
+            selection: None,
+            preview: &[],
+            animations: None,
+        }
+    }
+
+    fn screen_position(&self, position: Axial) -> Vector2<f32> {
+        flat_hex_to_pixel(position, self.scale) + self.offset
+    }
+
+    fn is_selected(&self, position: Axial) -> bool {
+        self.reveal == Reveal::Editing
+            && (self
+                .selection
+                .is_some_and(|selection| selection.contains(position))
+                || self.preview.contains(&position))
+    }
+}
+
+/// Draws the cells of a board, their counts and its line hints. Cells are listed in sorted order
+/// so the same board always gives the same scene.
+pub fn draw_board(scene: &mut Scene, board: &Board) {
+    let level = board.level;
+    let theme = board.theme;
+    let text_color = theme.text_color();
+    let positions = level.sorted_positions();
+
+    let hovered_line = board
+        .hovered
+        .and_then(|hovered| {
+            let hint = level.line_hints.get(&hovered)?;
+            Some(level.line_cells(hovered, hint.direction))
+        })
+        .unwrap_or_default();
+
+    let animation = |position| {
+        board
+            .animations
+            .map(|animations| animations.cell(position))
+            .unwrap_or_default()
+    };
+
+    for position in &positions {
+        let cell = &level.cells[position];
+        let animation = animation(*position);
+        let line_hover = if hovered_line.contains(position) {
+            LINE_HOVER
+        } else {
+            0.0
+        };
+        let revealed = match board.reveal {
+            Reveal::Editing | Reveal::Solution => true,
+            Reveal::Playing => cell.is_revealed() && animation.shows_revealed,
+            Reveal::Start => cell.start_revealed,
+        };
+        let mut flags = pattern_flags(theme, cell.hex, revealed);
+
+        if board.is_selected(*position) {
+            flags |= SELECTED;
+        }
+
+        if board.reveal == Reveal::Editing && cell.start_revealed {
+            flags |= START_REVEALED;
+        }
+
+        scene.hexes.push(DrawHex {
+            center: board.screen_position(*position) + animation.offset * board.scale,
+            stretch: animation.stretch,
+            color: theme.cell_color(cell.hex, revealed),
+            hover: animation.hover.max(line_hover),
+            flags,
+        });
+    }
+
+    for position in &positions {
+        let cell = &level.cells[position];
+
+        // Counts show up once the cell has flipped over.
+        let shown = match board.reveal {
+            Reveal::Editing | Reveal::Solution => true,
+            Reveal::Playing => cell.is_revealed() && !animation(*position).flipping,
+            Reveal::Start => cell.start_revealed,
+        };
+
+        if !shown {
+            continue;
+        }
+
+        let count = board.hints.cell_count(*position).unwrap_or(0);
+        let label = match cell.hex {
+            Hex::Empty {
+                show_neighbor_count: true,
+                modifier,
+            } => modifier.decorate(count),
+            Hex::Marked { show_around: true } => count.to_string(),
+            _ => continue,
+        };
+
+        scene.centered_text(
+            label,
+            board.screen_position(*position),
+            board.scale / 2.0,
+            text_color,
+        );
+    }
+
+    let mut origins: Vec<Axial> = level.line_hints.keys().copied().collect();
+    origins.sort_by_key(|origin| (origin.x, origin.y));
+
+    for origin in origins {
+        let hint = level.line_hints[&origin];
+        let count = board.hints.line_count(origin).unwrap_or(0);
+
+        let color = if board.is_selected(origin) || board.hovered == Some(origin) {
+            SELECTION_TEXT_COLOR
+        } else {
+            text_color
+        };
+
+        // Hints sit outside the board, turned to face along the line they count.
+        scene.rotated_text(
+            hint.modifier.decorate(count),
+            board.screen_position(origin),
+            board.scale / 2.0,
+            color,
+            flat_line_hint_rotation(hint.direction),
+        );
+    }
+}
+
+/// What the editor shows over the board.
+#[derive(Clone, Copy)]
+pub struct EditorOverlays<'a> {
+    /// The newest analysis of the level, if one has finished.
+    pub analysis: Option<&'a LiveAnalysis>,
+    /// Whether the analysis is for the level as it is now.
+    pub analysis_current: bool,
+    pub symmetry: Symmetry,
+    pub symmetry_center: Axial,
+    /// The inspected cell and the row of the inspector edited with the keyboard.
+    pub inspected: Option<(Axial, usize)>,
+    pub diagnostics: &'a [Diagnostic],
+}
+
+/// Everything the game shows besides its interface.
+#[derive(Clone, Copy)]
+pub struct View<'a> {
+    pub board: Board<'a>,
+    /// Width and height of the window, in pixels.
+    pub size: Vector2<f32>,
+    /// Where the selection lands while it's being moved.
+    pub move_preview: &'a [Axial],
+    pub mistakes: u32,
+    /// Shown while editing and left out while playing.
+    pub editor: Option<EditorOverlays<'a>>,
+    /// A message along the bottom edge.
+    pub status: Option<&'a str>,
+}
+
+/// Builds the scene for a view of the game.
+pub fn view_scene(view: &View) -> Scene {
+    let board = &view.board;
+    let mut scene = Scene::new(view.size, board.scale, board.theme);
+
+    draw_board(&mut scene, board);
+
+    for position in view.move_preview {
+        scene.hexes.push(DrawHex::new(
+            board.screen_position(*position),
+            Vector3::new(0.4, 0.4, 0.4),
+            0,
+        ));
+    }
+
+    let mistakes = format!("Mistakes: {}", view.mistakes);
+    let mistakes_position = Vector2::new(view.size.x - 250.0, 100.0);
+    scene.text(mistakes, mistakes_position, 48.0, board.theme.text_color());
+
+    if let Some(editor) = &view.editor {
+        add_live_analysis(&mut scene, board, editor);
+        add_symmetry(&mut scene, board, editor);
+        add_inspector(&mut scene, view, editor);
+        add_diagnostics(&mut scene, view, editor);
+    }
+
+    if let Some(message) = view.status {
+        let center = Vector2::new(view.size.x / 2.0, view.size.y - 28.0);
+        scene.centered_text(message, center, 24.0, board.theme.text_color());
+    }
+
+    scene
+}
+
+/// Marks the hidden cells that can be deduced right away and the ones the solver gets stuck
+/// on, and shows whether the level is solvable and unique in the top left corner.
+fn add_live_analysis(scene: &mut Scene, board: &Board, editor: &EditorOverlays) {
+    let analysis = match editor.analysis {
+        Some(analysis) => analysis,
+        None => return,
+    };
+
+    let marker_offset = Vector2::new(-board.scale * 0.5, board.scale * 0.5);
+
+    let mut deducible: Vec<Axial> = analysis
+        .steps
+        .iter()
+        .filter(|(_, step)| **step == 0)
+        .map(|(position, _)| *position)
+        .collect();
+    deducible.sort_by_key(|position| (position.x, position.y));
+
+    let markers = deducible
+        .into_iter()
+        .map(|position| (position, "+", [0.3, 1.0, 0.3, 1.0]))
+        .chain(
+            analysis
+                .stuck
+                .iter()
+                .map(|position| (*position, "?", [1.0, 0.25, 0.25, 1.0])),
+        );
+
+    for (position, symbol, color) in markers {
+        // The analysis can be for an older version of the level.
+        if !board.level.cells.contains_key(&position) {
+            continue;
+        }
+
+        let center = board.screen_position(position) + marker_offset;
+        scene.centered_text(symbol, center, board.scale / 2.0, color);
+    }
+
+    let status = if !editor.analysis_current {
+        "Analyzing...".to_string()
+    } else {
+        let solvable = if analysis.is_solvable() {
+            "solvable".to_string()
+        } else {
+            format!("stuck on {} cells", analysis.stuck.len())
+        };
+
+        let unique = if analysis.is_unique() {
+            "unique"
+        } else {
+            "not unique"
+        };
+
+        format!("{}, {}", solvable, unique)
+    };
+
+    let text_color = board.theme.text_color();
+    scene.text(status, Vector2::new(16.0, 16.0), 24.0, text_color);
+}
+
+/// Marks the symmetry center and names the symmetry below the solver status.
+fn add_symmetry(scene: &mut Scene, board: &Board, editor: &EditorOverlays) {
+    if editor.symmetry == Symmetry::Off {
+        return;
+    }
+
+    let color = [0.3, 0.9, 1.0, 1.0];
+    let center = board.screen_position(editor.symmetry_center);
+
+    scene.centered_text("*", center, board.scale, color);
+
+    let label = format!("Symmetry: {}", editor.symmetry.name());
+    scene.text(label, Vector2::new(16.0, 44.0), 24.0, color);
+}
+
+/// Lists the inspected cell's properties along the right edge, pointing out the row edited
+/// with the keyboard.
+fn add_inspector(scene: &mut Scene, view: &View, editor: &EditorOverlays) {
+    let text_color = view.board.theme.text_color();
+
+    let (position, row, cell) = match editor
+        .inspected
+        .and_then(|(position, row)| Some((position, row, view.board.level.cells.get(&position)?)))
+    {
+        Some(inspected) => inspected,
+        None => return,
+    };
+
+    let heading = format!("Cell at ({}, {})", position.x, position.y);
+    let rows = inspector::properties(cell)
+        .iter()
+        .enumerate()
+        .map(|(index, property)| {
+            let text = inspector::describe(cell, position, *property);
+
+            if index == row {
+                (format!("> {}", text), SELECTION_TEXT_COLOR)
+            } else {
+                (format!("  {}", text), text_color)
+            }
+        });
+
+    for (row, (text, color)) in std::iter::once((heading, [0.7, 0.7, 0.7, 1.0]))
+        .chain(rows)
+        .enumerate()
+    {
+        let top_left = inspector::row_position(view.size.x, row);
+        scene.text(text, top_left, inspector::ROW_HEIGHT * 0.8, color);
+    }
+}
+
+/// Marks the cells with problems and lists the problems in the bottom left corner.
+fn add_diagnostics(scene: &mut Scene, view: &View, editor: &EditorOverlays) {
+    let board = &view.board;
+    let marker_offset = Vector2::new(board.scale * 0.5, -board.scale * 0.5);
+
+    for diagnostic in editor.diagnostics {
+        let color = severity_color(diagnostic.severity());
+
+        for position in &diagnostic.positions {
+            let center = board.screen_position(*position) + marker_offset;
+            scene.centered_text("!", center, board.scale / 2.0, color);
+        }
+    }
+
+    let line_height = 24.0;
+    let lines = editor.diagnostics.len() as f32;
+
+    for (index, diagnostic) in editor.diagnostics.iter().enumerate() {
+        let color = severity_color(diagnostic.severity());
+        let top = view.size.y - 16.0 - (lines - index as f32) * line_height;
+
+        scene.text(
+            diagnostic.to_string(),
+            Vector2::new(16.0, top),
+            line_height * 0.8,
+            color,
+        );
+    }
+}
+
+fn severity_color(severity: Severity) -> [f32; 4] {
+    match severity {
+        Severity::Warning => [1.0, 0.8, 0.2, 1.0],
+        Severity::Error => [1.0, 0.25, 0.25, 1.0],
+    }
+}
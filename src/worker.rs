@@ -0,0 +1,145 @@
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+};
+
+use crate::{
+    daily::{daily_level, Date},
+    generator::{generate, GeneratorParams},
+    level::Level,
+};
+
+/// A level to build away from the window, as generating and pruning can take seconds.
+#[derive(Debug, Clone)]
+pub enum LevelRequest {
+    Generate { seed: u64, params: GeneratorParams },
+    Daily(Date),
+}
+
+impl LevelRequest {
+    fn build(&self) -> Level {
+        match self {
+            LevelRequest::Generate { seed, params } => generate(*seed, params),
+            LevelRequest::Daily(date) => daily_level(*date),
+        }
+    }
+}
+
+/// Builds levels on a background thread. Like `LiveSolver`, only the newest request is built
+/// once the thread is free, and a level finished for an older request is dropped, so asking
+/// again replaces what was asked before.
+pub struct LevelWorker {
+    jobs: Sender<(u64, LevelRequest)>,
+    results: Receiver<(u64, LevelRequest, Level)>,
+    generation: u64,
+    /// The generation of the last level handed out by `poll`.
+    finished: u64,
+}
+
+impl LevelWorker {
+    pub fn new() -> LevelWorker {
+        let (jobs, job_receiver) = channel::<(u64, LevelRequest)>();
+        let (result_sender, results) = channel();
+
+        thread::spawn(move || {
+            while let Ok(mut job) = job_receiver.recv() {
+                while let Ok(newer) = job_receiver.try_recv() {
+                    job = newer;
+                }
+
+                let (generation, request) = job;
+                let level = request.build();
+
+                if result_sender.send((generation, request, level)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        LevelWorker {
+            jobs,
+            results,
+            generation: 0,
+            finished: 0,
+        }
+    }
+
+    /// Queues a level to build, replacing any request still waiting.
+    pub fn submit(&mut self, request: LevelRequest) {
+        self.generation += 1;
+        let _ = self.jobs.send((self.generation, request));
+    }
+
+    /// Picks up the level for the last request once it's built.
+    pub fn poll(&mut self) -> Option<(LevelRequest, Level)> {
+        let mut newest = None;
+
+        while let Ok((generation, request, level)) = self.results.try_recv() {
+            if generation == self.generation {
+                newest = Some((request, level));
+            }
+        }
+
+        if newest.is_some() {
+            self.finished = self.generation;
+        }
+
+        newest
+    }
+
+    /// Whether a requested level is still being built.
+    pub fn is_pending(&self) -> bool {
+        self.finished != self.generation
+    }
+}
+
+impl Default for LevelWorker {
+    fn default() -> Self {
+        LevelWorker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::formats::{write_level, Format};
+
+    fn wait(worker: &mut LevelWorker) -> (LevelRequest, Level) {
+        let deadline = Instant::now() + Duration::from_secs(60);
+
+        loop {
+            if let Some(result) = worker.poll() {
+                return result;
+            }
+
+            assert!(Instant::now() < deadline, "no level arrived");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn only_the_last_request_is_handed_out() {
+        let params = GeneratorParams::default();
+        let mut worker = LevelWorker::new();
+
+        for seed in 1..=3 {
+            worker.submit(LevelRequest::Generate {
+                seed,
+                params: params.clone(),
+            });
+        }
+        assert!(worker.is_pending());
+
+        let (request, level) = wait(&mut worker);
+
+        assert!(matches!(request, LevelRequest::Generate { seed: 3, .. }));
+        assert_eq!(
+            write_level(&level, Format::Native).unwrap(),
+            write_level(&generate(3, &params), Format::Native).unwrap()
+        );
+        assert!(!worker.is_pending());
+        assert!(worker.poll().is_none());
+    }
+}
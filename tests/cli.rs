@@ -0,0 +1,90 @@
+//! Runs the `opencells` command line tool.
+
+mod common;
+
+use std::process::{Command, Output};
+
+fn opencells(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_opencells"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn level_path() -> String {
+    common::fixture_path("small.opencells")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn unknown_options_are_rejected() {
+    let output = opencells(&["render", &level_path(), "--solutoin"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown option: --solutoin"));
+}
+
+#[test]
+fn options_of_other_commands_are_rejected() {
+    let output = opencells(&["generate", "--seed", "1", "--solution"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("generate doesn't take --solution"));
+
+    let output = opencells(&["solve", &level_path(), "--seed", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("solve doesn't take --seed"));
+}
+
+#[test]
+fn scale_must_be_positive() {
+    for scale in &["0", "-3", "NaN", "inf"] {
+        let output = opencells(&["render", &level_path(), "--scale", scale]);
+
+        assert_eq!(output.status.code(), Some(2), "--scale {}", scale);
+        assert!(output.stdout.is_empty());
+    }
+
+    let output = opencells(&["render", &level_path(), "--scale", "12.5", "--solution"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("<svg"));
+}
+
+#[test]
+fn difficulty_must_be_a_number() {
+    for difficulty in &["-1", "NaN", "inf"] {
+        let output = opencells(&["generate", "--difficulty", difficulty]);
+
+        assert_eq!(output.status.code(), Some(2), "--difficulty {}", difficulty);
+        assert!(output.stdout.is_empty());
+    }
+
+    let output = opencells(&[
+        "generate",
+        "--seed",
+        "1",
+        "--difficulty",
+        "5",
+        "--candidates",
+        "2",
+    ]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn density_must_be_a_fraction() {
+    for density in &["-0.1", "1.5", "NaN", "inf"] {
+        let output = opencells(&["generate", "--seed", "1", "--density", density]);
+
+        assert_eq!(output.status.code(), Some(2), "--density {}", density);
+        assert!(output.stdout.is_empty());
+    }
+
+    for density in &["0", "0.2", "1"] {
+        let output = opencells(&["generate", "--seed", "1", "--density", density]);
+        assert!(output.status.success(), "--density {}", density);
+    }
+}
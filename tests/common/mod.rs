@@ -0,0 +1,111 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+use opencells_rs::{
+    formats::{read_level, Format},
+    level::Level,
+    scene::Scene,
+};
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect()
+}
+
+pub fn fixture(name: &str) -> String {
+    let path = fixture_path(name);
+    fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error))
+}
+
+/// Reads a fixture level in the native format.
+pub fn level(name: &str) -> Level {
+    read_level(&fixture(name), Format::Native).unwrap()
+}
+
+/// Compares `actual` against a saved fixture. Run with `OPENCELLS_UPDATE_GOLDEN=1` to save new
+/// fixtures after an intended change, and check the differences before committing them.
+pub fn assert_golden(name: &str, actual: &[u8]) {
+    let path = fixture_path(name);
+
+    if env::var_os("OPENCELLS_UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let golden = fs::read(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+
+    if golden != actual {
+        let actual_path = env::temp_dir().join(name);
+        fs::write(&actual_path, actual).unwrap();
+        panic!(
+            "{} doesn't match the saved fixture, see {}",
+            name,
+            actual_path.display()
+        );
+    }
+}
+
+/// Lists everything in a scene, one item per line with numbers rounded so the dump stays stable.
+pub fn dump(scene: &Scene) -> String {
+    let mut text = String::new();
+    let color = |color: &[f32]| {
+        color
+            .iter()
+            .map(|channel| format!("{:.3}", channel))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    writeln!(text, "size {:.1} {:.1}", scene.size.x, scene.size.y).unwrap();
+    writeln!(text, "clear {}", color(&scene.clear_color)).unwrap();
+    writeln!(text, "hex size {:.1}", scene.hex_size).unwrap();
+
+    for hex in &scene.hexes {
+        writeln!(
+            text,
+            "hex {:.1} {:.1} stretch {:.2} {:.2} color {} hover {:.2} flags {}",
+            hex.center.x,
+            hex.center.y,
+            hex.stretch.x,
+            hex.stretch.y,
+            color(&[hex.color.x, hex.color.y, hex.color.z]),
+            hex.hover,
+            hex.flags
+        )
+        .unwrap();
+    }
+
+    for draw in scene.texts.iter().chain(&scene.overlay_texts) {
+        writeln!(
+            text,
+            "text {:?} {:.1} {:.1} size {:.1} {:?} turned {:.1} color {}",
+            draw.text,
+            draw.position.x,
+            draw.position.y,
+            draw.size,
+            draw.anchor,
+            draw.rotation,
+            color(&draw.color)
+        )
+        .unwrap();
+    }
+
+    for rect in &scene.rects {
+        writeln!(
+            text,
+            "rect {:.1} {:.1} {:.1} {:.1} color {}",
+            rect.position.x,
+            rect.position.y,
+            rect.size.x,
+            rect.size.y,
+            color(&rect.color)
+        )
+        .unwrap();
+    }
+
+    text
+}
@@ -0,0 +1,16 @@
+//! Checks that the daily level for a date doesn't change between versions.
+
+mod common;
+
+use opencells_rs::{
+    daily::{daily_level, Date},
+    formats::{write_level, Format},
+};
+
+#[test]
+fn daily_level_matches_saved_level() {
+    let level = daily_level(Date::parse("2024-02-29").unwrap());
+    let text = write_level(&level, Format::Native).unwrap();
+
+    common::assert_golden("daily-2024-02-29.opencells", text.as_bytes());
+}
@@ -0,0 +1,209 @@
+//! Moves fixture levels between the formats and feeds the readers broken files.
+
+mod common;
+
+use opencells_rs::{
+    formats::{fits_hexcells_grid, read_level, write_level, Format, FormatError},
+    level::Level,
+};
+
+/// Everything about a level that all formats keep, in a stable order. Positions are relative
+/// to the first cell, as formats with a fixed grid move the level around.
+fn summary(level: &Level) -> Vec<String> {
+    let first = level.sorted_positions()[0];
+
+    let mut items: Vec<String> = level
+        .cells
+        .iter()
+        .map(|(position, cell)| {
+            let position = position - first;
+            format!(
+                "cell {} {} {:?} revealed {}",
+                position.x, position.y, cell.hex, cell.start_revealed
+            )
+        })
+        .chain(level.line_hints.iter().map(|(origin, hint)| {
+            let origin = origin - first;
+            format!(
+                "line {} {} {:?} {:?}",
+                origin.x, origin.y, hint.direction, hint.modifier
+            )
+        }))
+        .collect();
+
+    items.sort();
+    items
+}
+
+fn convert(level: &Level, format: Format) -> Level {
+    let text = write_level(level, format).unwrap();
+    read_level(&text, format)
+        .unwrap_or_else(|error| panic!("{}: {}\n{}", format.name(), error, text))
+}
+
+#[test]
+fn levels_survive_every_format() {
+    for name in &["small.opencells", "formats.opencells"] {
+        let level = common::level(name);
+        let expected = summary(&level);
+
+        for format in &Format::ALL {
+            assert_eq!(
+                summary(&convert(&level, *format)),
+                expected,
+                "{} as {}",
+                name,
+                format.name()
+            );
+        }
+
+        // Through every format in turn, and back to the start.
+        let mut converted = convert(&level, Format::Hexcells);
+        converted = convert(&converted, Format::SixCells);
+        converted = convert(&converted, Format::Native);
+        converted = convert(&converted, Format::SixCells);
+        converted = convert(&converted, Format::Hexcells);
+        assert_eq!(
+            summary(&converted),
+            expected,
+            "{} through every format",
+            name
+        );
+    }
+}
+
+#[test]
+fn level_text_survives_every_format() {
+    let level = common::level("formats.opencells");
+
+    for format in &Format::ALL {
+        let converted = convert(&level, *format);
+        assert_eq!(converted.info.title, "Formats", "{}", format.name());
+        assert_eq!(converted.info.author, "Someone", "{}", format.name());
+    }
+}
+
+/// Reads broken text, returning the line and reason of the error.
+fn error(text: &str, format: Format) -> (usize, String) {
+    match read_level(text, format) {
+        Ok(_) => panic!("read broken {} text:\n{}", format.name(), text),
+        Err(FormatError::Malformed { line, reason }) => (line, reason),
+        Err(error) => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn malformed_native_text_is_rejected() {
+    let item = |line: &str| error(&format!("OpenCells level v1\n\n{}\n", line), Format::Native);
+
+    assert_eq!(error("", Format::Native).0, 1);
+    assert_eq!(error("OpenCells level v2\n", Format::Native).0, 1);
+    assert_eq!(item("cell 0"), (3, "expected a coordinate".to_string()));
+    assert_eq!(item("cell a b empty").0, 3);
+    assert_eq!(
+        item("cell 0 0 purple"),
+        (3, "unknown cell kind `purple`".to_string())
+    );
+    assert_eq!(item("cell 0 0 empty sparkly").0, 3);
+    assert_eq!(item("line 0 0 up").0, 3);
+    assert_eq!(item("frobnicate").0, 3);
+}
+
+#[test]
+fn malformed_hexcells_text_is_rejected() {
+    let level = common::level("small.opencells");
+    let text = write_level(&level, Format::Hexcells).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(error("", Format::Hexcells).0, 1);
+    assert_eq!(error(&text.replacen("v1", "v2", 1), Format::Hexcells).0, 1);
+
+    // Missing rows.
+    let truncated = lines[..20].join("\n");
+    assert!(read_level(&truncated, Format::Hexcells).is_err());
+
+    // A slot that isn't a cell, a line hint or nothing.
+    let row = lines
+        .iter()
+        .position(|line| line.contains('o') || line.contains('x'))
+        .unwrap();
+    let mut broken: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    let column = broken[row].find(&['o', 'x'][..]).unwrap();
+    broken[row].replace_range(column..column + 1, "?");
+    assert_eq!(error(&broken.join("\n"), Format::Hexcells).0, row + 1);
+}
+
+#[test]
+fn malformed_sixcells_text_is_rejected() {
+    assert_eq!(error("", Format::SixCells).0, 1);
+    assert_eq!(error("{\n\"cells\": [\n}", Format::SixCells).0, 3);
+    assert_eq!(error("[]", Format::SixCells).1, "missing cells");
+    assert_eq!(
+        error(r#"{"cells": [{"kind": 0}]}"#, Format::SixCells).1,
+        "cell without a position"
+    );
+    assert_eq!(
+        error(
+            r#"{"cells": [{"x": 0, "y": 0, "kind": 7}]}"#,
+            Format::SixCells
+        )
+        .1,
+        "cell without a known kind"
+    );
+    assert_eq!(
+        error(
+            r#"{"cells": [], "columns": [{"x": 0, "y": 0, "angle": 45}]}"#,
+            Format::SixCells
+        )
+        .1,
+        "column with an unsupported angle"
+    );
+
+    // Deeply nested documents are turned down instead of overflowing the stack.
+    let nested = "[".repeat(100_000);
+    assert_eq!(error(&nested, Format::SixCells).0, 1);
+}
+
+#[test]
+fn levels_outside_the_hexcells_grid_are_rejected() {
+    let level = read_level(
+        "OpenCells level v1\ncell 0 0 empty\ncell 40 0 empty\n",
+        Format::Native,
+    )
+    .unwrap();
+
+    assert_eq!(
+        write_level(&level, Format::Hexcells).unwrap_err(),
+        FormatError::TooLarge
+    );
+    assert!(!fits_hexcells_grid(&level));
+}
+
+#[test]
+fn grid_check_agrees_with_the_writer() {
+    // Columns and doubled rows each just inside and just outside the grid, away from the origin.
+    let pairs = [
+        ("-5 3", "27 3"),
+        ("-5 3", "28 3"),
+        ("2 -9", "2 7"),
+        ("2 -9", "2 8"),
+        ("3 -9", "2 8"),
+        ("0 0", "-1 16"),
+    ];
+
+    for (first, second) in pairs.iter() {
+        let text = format!(
+            "OpenCells level v1\ncell {} empty\ncell {} marked\n",
+            first, second
+        );
+        let level = read_level(&text, Format::Native).unwrap();
+
+        assert_eq!(
+            fits_hexcells_grid(&level),
+            write_level(&level, Format::Hexcells).is_ok(),
+            "{} and {}",
+            first,
+            second
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Draws fixture levels and compares them against saved images.
+
+mod common;
+
+use opencells_rs::{
+    raster::rasterize,
+    scene::{level_scene, Scene},
+    theme::Theme,
+};
+
+#[test]
+fn small_level_matches_golden_image() {
+    let level = common::level("small.opencells");
+
+    let scene = level_scene(&level, 40.0, false, &Theme::dark());
+    common::assert_golden("small.png", &rasterize(&scene).unwrap().to_png());
+
+    let scene = level_scene(&level, 40.0, true, &Theme::print());
+    common::assert_golden("small-solution.png", &rasterize(&scene).unwrap().to_png());
+}
+
+#[test]
+fn huge_scenes_are_rejected() {
+    let scene = Scene::new([100_000.0, 100_000.0].into(), 40.0, &Theme::dark());
+    let error = rasterize(&scene).unwrap_err();
+
+    assert_eq!((error.width, error.height), (100_000, 100_000));
+}
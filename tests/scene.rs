@@ -0,0 +1,73 @@
+//! Checks the scenes built for fixture levels, against a saved dump and by hand.
+
+mod common;
+
+use opencells_rs::{
+    hexagon::Axial,
+    scene::{level_scene, Anchor},
+    theme::Theme,
+};
+
+#[test]
+fn small_level_scene_matches_dump() {
+    let level = common::level("small.opencells");
+
+    let scene = level_scene(&level, 40.0, false, &Theme::dark());
+    common::assert_golden("small.scene.txt", common::dump(&scene).as_bytes());
+
+    let scene = level_scene(&level, 40.0, true, &Theme::dark());
+    common::assert_golden("small-solution.scene.txt", common::dump(&scene).as_bytes());
+}
+
+#[test]
+fn small_level_scene_shows_revealed_counts() {
+    let level = common::level("small.opencells");
+    let scene = level_scene(&level, 40.0, false, &Theme::dark());
+
+    assert_eq!(scene.hexes.len(), level.cells.len());
+    assert!(scene.rects.is_empty());
+    assert!(scene.overlay_texts.is_empty());
+
+    // Only the center cell starts revealed, and it's the only one labelled besides the line.
+    let theme = Theme::dark();
+    let center_cell = &level.cells[&Axial::new(0, 0)];
+    let revealed: Vec<_> = scene
+        .hexes
+        .iter()
+        .filter(|hex| hex.color == theme.cell_color(center_cell.hex, true))
+        .collect();
+    assert_eq!(revealed.len(), 1);
+
+    let texts: Vec<_> = scene
+        .texts
+        .iter()
+        .map(|text| (text.text.as_str(), text.anchor))
+        .collect();
+    assert_eq!(texts, [("{3}", Anchor::Center), ("1", Anchor::Center)]);
+
+    let center = scene.texts[0].position;
+    assert_eq!(center, revealed[0].center);
+
+    // The line hint sits above its column, upright for a line going down.
+    let line = &scene.texts[1];
+    assert_eq!(line.rotation, 0.0);
+    assert!((line.position.x - center.x).abs() < 0.01);
+    assert!(line.position.y < center.y);
+
+    // Everything is inside the frame.
+    for hex in &scene.hexes {
+        assert!(hex.center.x > 0.0 && hex.center.x < scene.size.x);
+        assert!(hex.center.y > 0.0 && hex.center.y < scene.size.y);
+    }
+}
+
+#[test]
+fn solution_scene_labels_every_numbered_cell() {
+    let level = common::level("small.opencells");
+    let scene = level_scene(&level, 40.0, true, &Theme::dark());
+
+    let labels: Vec<_> = scene.texts.iter().map(|text| text.text.as_str()).collect();
+
+    // Numbered cells in position order with their modifiers, then the line hint.
+    assert_eq!(labels, ["2", "-2-", "{3}", "2", "1"]);
+}
@@ -0,0 +1,231 @@
+//! Checks the scenes the game draws while editing and playing, against saved dumps and by hand.
+
+mod common;
+
+use std::collections::HashMap;
+
+use cgmath::Vector2;
+
+use opencells_rs::{
+    animation::Animations,
+    hexagon::Axial,
+    hints::HintCache,
+    level::Level,
+    live::LiveAnalysis,
+    scene::{Scene, SELECTED, START_REVEALED},
+    selection::{SelectMode, Selection},
+    symmetry::Symmetry,
+    theme::Theme,
+    uniqueness::Uniqueness,
+    validate::validate,
+    view::{view_scene, Board, EditorOverlays, Reveal, View},
+};
+
+const SCALE: f32 = 40.0;
+const OFFSET: Vector2<f32> = Vector2::new(400.0, 300.0);
+/// The line hint of the small level, which counts the column below it.
+const LINE: Axial = Axial::new(0, -2);
+
+fn view<'a>(board: Board<'a>) -> View<'a> {
+    View {
+        board,
+        size: Vector2::new(1200.0, 800.0),
+        move_preview: &[],
+        mistakes: 0,
+        editor: None,
+        status: None,
+    }
+}
+
+fn board<'a>(
+    level: &'a Level,
+    hints: &'a HintCache,
+    theme: &'a Theme,
+    reveal: Reveal,
+) -> Board<'a> {
+    Board {
+        offset: OFFSET,
+        ..Board::new(level, hints, theme, SCALE, reveal)
+    }
+}
+
+/// The hexagon drawn for a cell, found by where it sits.
+fn hex_at(scene: &Scene, position: Axial) -> &opencells_rs::scene::DrawHex {
+    let center = opencells_rs::hexagon::flat_hex_to_pixel(position, SCALE) + OFFSET;
+
+    scene
+        .hexes
+        .iter()
+        .find(|hex| (hex.center - center).x.abs() < 0.01 && (hex.center - center).y.abs() < 0.01)
+        .unwrap_or_else(|| panic!("no hexagon at {:?}", position))
+}
+
+#[test]
+fn editing_view_marks_selection_and_hovered_line() {
+    let level = common::level("small.opencells");
+    let hints = HintCache::new(&level);
+    let theme = Theme::dark();
+
+    let mut selection = Selection::new();
+    selection.select(
+        vec![Axial::new(1, 0), Axial::new(-1, 1)],
+        SelectMode::Replace,
+    );
+    let preview = [Axial::new(0, 1)];
+
+    let scene = view_scene(&view(Board {
+        hovered: Some(LINE),
+        selection: Some(&selection),
+        preview: &preview,
+        ..board(&level, &hints, &theme, Reveal::Editing)
+    }));
+    common::assert_golden("small-editing.view.txt", common::dump(&scene).as_bytes());
+
+    for position in level.sorted_positions() {
+        let hex = hex_at(&scene, position);
+        let selected = selection.contains(position) || preview.contains(&position);
+
+        assert_eq!(hex.flags & SELECTED != 0, selected, "{:?}", position);
+        assert_eq!(
+            hex.flags & START_REVEALED != 0,
+            level.cells[&position].start_revealed,
+            "{:?}",
+            position
+        );
+
+        // Hovering the line hint lights up the column it counts.
+        let on_line = position.x == 0;
+        assert_eq!(hex.hover > 0.0, on_line, "{:?}", position);
+    }
+
+    // Every count shows while editing, and the hovered line hint stands out.
+    let line = &scene.texts[scene.texts.len() - 2];
+    assert_eq!(line.text, "1");
+    assert_ne!(line.color, theme.text_color());
+    assert_eq!(scene.texts.len(), 6);
+}
+
+#[test]
+fn hovered_cells_fade_in() {
+    let level = common::level("small.opencells");
+    let hints = HintCache::new(&level);
+    let theme = Theme::dark();
+    let hovered = Axial::new(1, 0);
+
+    let mut animations = Animations::new();
+    animations.advance(0.06, hovered);
+
+    let scene = view_scene(&view(Board {
+        hovered: Some(hovered),
+        animations: Some(&animations),
+        ..board(&level, &hints, &theme, Reveal::Editing)
+    }));
+    common::assert_golden("small-hover.view.txt", common::dump(&scene).as_bytes());
+
+    let halfway = hex_at(&scene, hovered).hover;
+    assert!(halfway > 0.0 && halfway < 1.0);
+
+    animations.advance(1.0, hovered);
+    let scene = view_scene(&view(Board {
+        hovered: Some(hovered),
+        animations: Some(&animations),
+        ..board(&level, &hints, &theme, Reveal::Editing)
+    }));
+
+    assert_eq!(hex_at(&scene, hovered).hover, 1.0);
+    assert_eq!(hex_at(&scene, Axial::new(0, 0)).hover, 0.0);
+}
+
+#[test]
+fn editor_overlays_match_dump() {
+    let mut level = common::level("small.opencells");
+    // A stray cell gives the level an island to warn about.
+    level.cells.insert(
+        Axial::new(5, 0),
+        opencells_rs::level::CellState::new(opencells_rs::level::Hex::Empty {
+            show_neighbor_count: false,
+            modifier: opencells_rs::level::HintModifier::Plain,
+        }),
+    );
+    let hints = HintCache::new(&level);
+    let theme = Theme::dark();
+    let diagnostics = validate(&level);
+    assert!(!diagnostics.is_empty());
+
+    let analysis = LiveAnalysis {
+        generation: 1,
+        steps: vec![(Axial::new(1, 0), 0), (Axial::new(0, -1), 1)]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        stuck: vec![Axial::new(5, 0)],
+        uniqueness: Uniqueness::Unique,
+    };
+
+    let view = View {
+        editor: Some(EditorOverlays {
+            analysis: Some(&analysis),
+            analysis_current: true,
+            symmetry: Symmetry::Rotate(3),
+            symmetry_center: Axial::new(0, 0),
+            inspected: Some((Axial::new(0, 0), 1)),
+            diagnostics: &diagnostics,
+        }),
+        status: Some("The level has a unique solution."),
+        ..view(board(&level, &hints, &theme, Reveal::Editing))
+    };
+    let scene = view_scene(&view);
+    common::assert_golden("small-overlays.view.txt", common::dump(&scene).as_bytes());
+
+    let texts: Vec<&str> = scene.texts.iter().map(|text| text.text.as_str()).collect();
+
+    assert!(texts.contains(&"stuck on 1 cells, unique"));
+    assert!(texts.contains(&"Symmetry: 3-fold rotation"));
+    assert!(texts.contains(&"Cell at (0, 0)"));
+    assert!(texts.contains(&"The level has a unique solution."));
+    // Only cells deducible right away get a marker, and stuck cells get theirs.
+    assert_eq!(texts.iter().filter(|text| **text == "+").count(), 1);
+    assert_eq!(texts.iter().filter(|text| **text == "?").count(), 1);
+    assert!(texts.iter().any(|text| text.starts_with("> ")));
+
+    // Nothing of the editor shows while playing.
+    let playing = view_scene(&View {
+        editor: None,
+        ..view
+    });
+    assert!(playing.texts.len() < scene.texts.len());
+}
+
+#[test]
+fn playing_view_hides_unrevealed_cells() {
+    let level = common::level("small.opencells");
+    let hints = HintCache::new(&level);
+    let theme = Theme::dark();
+
+    for cell in level.cells.values() {
+        cell.reset_revealed();
+    }
+    level.cells[&Axial::new(1, 0)].reveal();
+
+    let mut selection = Selection::new();
+    selection.select(level.sorted_positions(), SelectMode::Replace);
+
+    let scene = view_scene(&View {
+        mistakes: 2,
+        ..view(Board {
+            selection: Some(&selection),
+            ..board(&level, &hints, &theme, Reveal::Playing)
+        })
+    });
+    common::assert_golden("small-playing.view.txt", common::dump(&scene).as_bytes());
+
+    let labels: Vec<&str> = scene.texts.iter().map(|text| text.text.as_str()).collect();
+    assert_eq!(labels, ["{3}", "2", "1", "Mistakes: 2"]);
+
+    for position in level.sorted_positions() {
+        let cell = &level.cells[&position];
+        let hex = hex_at(&scene, position);
+
+        assert_eq!(hex.flags & (SELECTED | START_REVEALED), 0);
+        assert_eq!(hex.color, theme.cell_color(cell.hex, cell.is_revealed()));
+    }
+}